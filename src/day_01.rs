@@ -1,26 +1,419 @@
 use itertools::Itertools;
 use nom::IResult;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    io::{self, Write},
+};
 
-pub fn star_1(data: String) {
-    let values = parse(&data);
-    let count = values.iter().tuple_windows().filter(|(a, b)| a < b).count();
-    println!("{}", count);
+pub fn star_1(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    print_increases(&data, args, 1)
 }
 
-pub fn star_2(data: String) {
-    let values = parse(&data);
-    let sums = values.iter().tuple_windows().map(|(a, b, c)| a + b + c);
-    let count = sums.tuple_windows().filter(|(a, b)| a < b).count();
-    println!("{}", count);
+pub fn star_2(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    print_increases(&data, args, 3)
 }
 
-fn parse(data: &str) -> Vec<u32> {
-    super::utils::parse(numbers, data)
+/// Prints how every window compares to the one before it: the totals for each trend, where each
+/// change happened, and the longest run of increases. Windows hold a single depth by default.
+pub fn summary(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let changes = analysis(args, 1)?
+        .changes(depths(&data))
+        .collect::<Vec<_>>();
+    write_summary(&changes, io::stdout().lock())?;
+    Ok(())
 }
 
-fn numbers(input: &str) -> IResult<&str, Vec<u32>> {
-    use super::utils::lines;
+/// Prints how many windows of depths are greater than the one before. The options can be given
+/// after the file name: an aggregate, as `sum`, `mean` or `max`, which is `sum` by default, and
+/// `window=<size>` to override the star's window size.
+fn print_increases(data: &str, args: &[String], size: usize) -> Result<(), Box<dyn Error>> {
+    let report = analysis(args, size)?.report(depths(data));
+    println!("{}", report.increases);
+    Ok(())
+}
+
+/// The analysis the options ask for, with windows of `size` depths unless they say otherwise.
+fn analysis(args: &[String], size: usize) -> Result<Analysis, OptionError> {
+    let (mut size, mut aggregate) = (size, Aggregate::Sum);
+    for arg in args {
+        if let Some(value) = arg.strip_prefix("window=") {
+            size = match value.parse() {
+                Ok(size) if size > 0 => size,
+                _ => return Err(OptionError::Window(value.to_string())),
+            };
+        } else {
+            aggregate = arg.parse().map_err(|_| OptionError::Unknown(arg.clone()))?;
+        }
+    }
+    Ok(Analysis::new(size, aggregate))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OptionError {
+    Window(String),
+    Unknown(String),
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Window(size) => write!(f, "`{}` isn't a positive window size", size),
+            Self::Unknown(arg) => write!(
+                f,
+                "`{}` isn't an option, expected `sum`, `mean`, `max` or `window=<size>`",
+                arg
+            ),
+        }
+    }
+}
+
+impl Error for OptionError {}
+
+fn write_summary<W: Write>(changes: &[Change], mut out: W) -> io::Result<()> {
+    let report = changes.iter().copied().collect::<Report>();
+    let trends = [
+        ("increases", report.increases, Trend::Increase),
+        ("decreases", report.decreases, Trend::Decrease),
+        ("flat", report.flat, Trend::Flat),
+    ];
+    for (name, count, trend) in trends {
+        let positions = changes
+            .iter()
+            .filter(|change| change.trend == trend)
+            .map(|change| change.position)
+            .join(",");
+        writeln!(out, "{}: {} at [{}]", name, count, positions)?;
+    }
+    writeln!(
+        out,
+        "longest increasing run: {}",
+        report.longest_increasing_run
+    )
+}
+
+/// Parses one depth per line, lazily.
+fn depths(data: &str) -> impl Iterator<Item = u32> + '_ {
+    data.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| super::utils::parse(depth, line))
+}
+
+fn depth(input: &str) -> IResult<&str, u32> {
     use nom::character::complete::u32 as u32_;
 
-    lines(u32_)(input)
+    u32_(input)
+}
+
+/// How a window of depths is reduced to a single value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Aggregate {
+    Sum,
+    Mean,
+    Max,
+}
+
+impl std::str::FromStr for Aggregate {
+    type Err = UnknownAggregate;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum" => Ok(Self::Sum),
+            "mean" => Ok(Self::Mean),
+            "max" => Ok(Self::Max),
+            _ => Err(UnknownAggregate(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UnknownAggregate(String);
+
+impl fmt::Display for UnknownAggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` isn't an aggregate, expected `sum`, `mean` or `max`",
+            self.0
+        )
+    }
+}
+
+impl Error for UnknownAggregate {}
+
+/// Compares consecutive sliding windows of depths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Analysis {
+    size: usize,
+    aggregate: Aggregate,
+}
+
+impl Analysis {
+    fn new(size: usize, aggregate: Aggregate) -> Self {
+        assert!(size > 0, "windows must hold at least one depth");
+        Self { size, aggregate }
+    }
+
+    /// The aggregate of each full window, in order.
+    fn windows<I>(self, depths: I) -> Windows<I::IntoIter>
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        Windows {
+            analysis: self,
+            depths: depths.into_iter(),
+            window: VecDeque::with_capacity(self.size + 1),
+            sum: 0,
+            maxima: VecDeque::new(),
+        }
+    }
+
+    /// How each window compares to the one before it.
+    fn changes<I>(self, depths: I) -> impl Iterator<Item = Change>
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        let size = self.size;
+        self.windows(depths)
+            .scan(None, |prev, value| {
+                Some(prev.replace(value).map(|prev| (prev, value)))
+            })
+            .enumerate()
+            .filter_map(move |(window, pair)| {
+                let (prev, value) = pair?;
+                let trend = match prev.partial_cmp(&value)? {
+                    std::cmp::Ordering::Less => Trend::Increase,
+                    std::cmp::Ordering::Greater => Trend::Decrease,
+                    std::cmp::Ordering::Equal => Trend::Flat,
+                };
+                Some(Change {
+                    position: window + size - 1,
+                    trend,
+                })
+            })
+    }
+
+    fn report<I>(self, depths: I) -> Report
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        self.changes(depths).collect()
+    }
+}
+
+/// Sliding windows over a stream of depths, keeping only the current window in memory.
+#[derive(Debug, Clone)]
+struct Windows<I> {
+    analysis: Analysis,
+    depths: I,
+    window: VecDeque<u32>,
+    sum: u64,
+    /// The depths in the window that are at least as deep as every later one, deepest first.
+    maxima: VecDeque<u32>,
+}
+
+impl<I: Iterator<Item = u32>> Iterator for Windows<I> {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let depth = self.depths.next()?;
+            self.window.push_back(depth);
+            self.sum += u64::from(depth);
+            while self.maxima.back().is_some_and(|&max| max < depth) {
+                self.maxima.pop_back();
+            }
+            self.maxima.push_back(depth);
+
+            if self.window.len() > self.analysis.size {
+                let old = self.window.pop_front().unwrap();
+                self.sum -= u64::from(old);
+                if self.maxima.front() == Some(&old) {
+                    self.maxima.pop_front();
+                }
+            }
+
+            if self.window.len() == self.analysis.size {
+                return Some(match self.analysis.aggregate {
+                    Aggregate::Sum => self.sum as f64,
+                    Aggregate::Mean => self.sum as f64 / self.analysis.size as f64,
+                    Aggregate::Max => f64::from(self.maxima[0]),
+                });
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Trend {
+    Increase,
+    Decrease,
+    Flat,
+}
+
+/// How a window compares to the one before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Change {
+    /// The index of the depth that was added to the window.
+    position: usize,
+    trend: Trend,
+}
+
+/// Totals over every pair of consecutive windows.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct Report {
+    increases: usize,
+    decreases: usize,
+    flat: usize,
+    /// The most increases in a row.
+    longest_increasing_run: usize,
+}
+
+impl FromIterator<Change> for Report {
+    fn from_iter<I: IntoIterator<Item = Change>>(changes: I) -> Self {
+        let mut report = Report::default();
+        let mut run = 0;
+        for change in changes {
+            match change.trend {
+                Trend::Increase => report.increases += 1,
+                Trend::Decrease => report.decreases += 1,
+                Trend::Flat => report.flat += 1,
+            }
+            run = if change.trend == Trend::Increase {
+                run + 1
+            } else {
+                0
+            };
+            report.longest_increasing_run = report.longest_increasing_run.max(run);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [u32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    #[test]
+    fn example_counts_are_correct() {
+        let report = Analysis::new(1, Aggregate::Sum).report(EXAMPLE);
+        assert_eq!(7, report.increases);
+        assert_eq!(2, report.decreases);
+        assert_eq!(0, report.flat);
+        assert_eq!(3, report.longest_increasing_run);
+
+        let report = Analysis::new(3, Aggregate::Sum).report(EXAMPLE);
+        assert_eq!(5, report.increases);
+        assert_eq!(1, report.flat);
+    }
+
+    #[test]
+    fn example_parses_lazily() {
+        let data = EXAMPLE.iter().join("\n") + "\n";
+        assert_eq!(EXAMPLE.to_vec(), depths(&data).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn changes_are_positioned_at_the_new_depth() {
+        let changes = Analysis::new(3, Aggregate::Sum)
+            .changes(EXAMPLE)
+            .collect::<Vec<_>>();
+        let expected = [
+            (3, Trend::Increase),
+            (4, Trend::Flat),
+            (5, Trend::Decrease),
+            (6, Trend::Increase),
+            (7, Trend::Increase),
+            (8, Trend::Increase),
+            (9, Trend::Increase),
+        ];
+        let expected = expected
+            .map(|(position, trend)| Change { position, trend })
+            .to_vec();
+        assert_eq!(expected, changes);
+    }
+
+    #[test]
+    fn aggregates_match_naive_windows() {
+        let mut rng = crate::utils::Rng::new(0x9e37_79b9_7f4a_7c15);
+        let depths = (0..500).map(|_| rng.below(50) as u32).collect::<Vec<_>>();
+
+        for size in 1..=6 {
+            for aggregate in [Aggregate::Sum, Aggregate::Mean, Aggregate::Max] {
+                let expected = depths
+                    .windows(size)
+                    .map(|window| match aggregate {
+                        Aggregate::Sum => window.iter().sum::<u32>() as f64,
+                        Aggregate::Mean => window.iter().sum::<u32>() as f64 / size as f64,
+                        Aggregate::Max => *window.iter().max().unwrap() as f64,
+                    })
+                    .collect::<Vec<_>>();
+                let actual = Analysis::new(size, aggregate)
+                    .windows(depths.iter().copied())
+                    .collect::<Vec<_>>();
+                assert_eq!(expected, actual);
+            }
+        }
+    }
+
+    #[test]
+    fn short_inputs_have_no_changes() {
+        let analysis = Analysis::new(4, Aggregate::Max);
+        assert_eq!(Report::default(), analysis.report([1, 2, 3]));
+        assert_eq!(Report::default(), analysis.report([1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn aggregates_are_parsed() {
+        assert_eq!(Ok(Aggregate::Mean), "mean".parse());
+        assert_eq!(Ok(Aggregate::Max), "max".parse());
+        let err = "median".parse::<Aggregate>().unwrap_err();
+        assert_eq!(
+            "`median` isn't an aggregate, expected `sum`, `mean` or `max`",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn options_are_parsed() {
+        let args = |args: &[&str], size| {
+            analysis(
+                &args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>(),
+                size,
+            )
+        };
+        assert_eq!(Ok(Analysis::new(3, Aggregate::Sum)), args(&[], 3));
+        assert_eq!(
+            Ok(Analysis::new(5, Aggregate::Max)),
+            args(&["max", "window=5"], 3)
+        );
+        assert_eq!(
+            Err(OptionError::Window("0".to_string())),
+            args(&["window=0"], 1)
+        );
+        let err = args(&["median"], 1).unwrap_err();
+        assert_eq!(
+            "`median` isn't an option, expected `sum`, `mean`, `max` or `window=<size>`",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn summary_lists_where_each_change_happened() {
+        let changes = Analysis::new(3, Aggregate::Sum)
+            .changes(EXAMPLE)
+            .collect::<Vec<_>>();
+        let mut out = Vec::new();
+        write_summary(&changes, &mut out).unwrap();
+        let expected = "\
+increases: 5 at [3,6,7,8,9]
+decreases: 1 at [5]
+flat: 1 at [4]
+longest increasing run: 4
+";
+        assert_eq!(expected, String::from_utf8(out).unwrap());
+    }
 }
@@ -1,52 +1,194 @@
 use nom::IResult;
+use std::{error::Error, fmt};
 
-pub fn star_1(data: String) {
+pub fn star_1(data: String) -> Result<(), Box<dyn Error>> {
+    print_product(data, Plain)
+}
+
+pub fn star_2(data: String) -> Result<(), Box<dyn Error>> {
+    print_product(data, Aim)
+}
+
+/// Prints the state after each command as CSV. The model can be given after the file name, as
+/// `plain` for star 1's or `aim` for star 2's, and is `aim` by default.
+pub fn trajectory(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let states = states(parse(&data), args)?;
+    println!("horizontal,depth,aim");
+    for state in states {
+        println!("{},{},{}", state.horizontal, state.depth, state.aim);
+    }
+    Ok(())
+}
+
+/// The state after each of `commands`, under the model named in `args`.
+fn states(commands: Vec<Command>, args: &[String]) -> Result<Vec<State>, Box<dyn Error>> {
+    let states = match args.first().map(String::as_str) {
+        Some("plain") => Submarine::new(Plain).trajectory(commands)?,
+        Some("aim") | None => Submarine::new(Aim).trajectory(commands)?,
+        Some(model) => return Err(UnknownModel(model.to_string()).into()),
+    };
+    Ok(states)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UnknownModel(String);
+
+impl fmt::Display for UnknownModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` isn't a model, expected `plain` or `aim`", self.0)
+    }
+}
+
+impl Error for UnknownModel {}
+
+fn print_product<M: Model>(data: String, model: M) -> Result<(), Box<dyn Error>> {
     let commands = parse(&data);
-    let mut horiz = 0;
-    let mut depth = 0;
+    let mut submarine = Submarine::new(model);
     for command in commands {
+        submarine.execute(command)?;
+    }
+    let state = submarine.state;
+    println!("{}", i128::from(state.horizontal) * i128::from(state.depth));
+    Ok(())
+}
+
+fn parse(data: &str) -> Vec<Command> {
+    super::utils::parse(commands, data)
+}
+
+fn commands(input: &str) -> IResult<&str, Vec<Command>> {
+    super::utils::lines(command)(input)
+}
+
+/// Where the submarine is and which way it's pointing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct State {
+    horizontal: i64,
+    depth: i64,
+    aim: i64,
+}
+
+/// How commands move the submarine.
+trait Model {
+    /// The state after `command`, or `None` if it overflows.
+    fn apply(&self, state: State, command: Command) -> Option<State>;
+}
+
+/// `down` and `up` change the depth directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Plain;
+
+impl Model for Plain {
+    fn apply(&self, mut state: State, command: Command) -> Option<State> {
+        let distance = i64::try_from(command.distance).ok()?;
         match command.direction {
-            Direction::Forward => horiz += command.distance,
-            Direction::Down => depth += command.distance,
-            Direction::Up => depth -= command.distance,
+            Direction::Forward => state.horizontal = state.horizontal.checked_add(distance)?,
+            Direction::Back => state.horizontal = state.horizontal.checked_sub(distance)?,
+            Direction::Down => state.depth = state.depth.checked_add(distance)?,
+            Direction::Up => state.depth = state.depth.checked_sub(distance)?,
         }
+        Some(state)
     }
-    println!("{}", horiz * depth);
 }
 
-pub fn star_2(data: String) {
-    let commands = parse(&data);
-    let mut horiz = 0;
-    let mut depth = 0;
-    let mut aim = 0;
-    for command in commands {
-        let dist = command.distance as i64;
+/// `down` and `up` change the aim, and moving changes the depth by the aim times the distance.
+/// Moving back retraces a forward move, so it changes the depth the other way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Aim;
+
+impl Model for Aim {
+    fn apply(&self, mut state: State, command: Command) -> Option<State> {
+        let distance = i64::try_from(command.distance).ok()?;
         match command.direction {
             Direction::Forward => {
-                horiz += dist;
-                depth += aim * dist;
+                state.horizontal = state.horizontal.checked_add(distance)?;
+                state.depth = state.depth.checked_add(state.aim.checked_mul(distance)?)?;
+            }
+            Direction::Back => {
+                state.horizontal = state.horizontal.checked_sub(distance)?;
+                state.depth = state.depth.checked_sub(state.aim.checked_mul(distance)?)?;
             }
-            Direction::Down => aim += dist,
-            Direction::Up => aim -= dist,
+            Direction::Down => state.aim = state.aim.checked_add(distance)?,
+            Direction::Up => state.aim = state.aim.checked_sub(distance)?,
         }
+        Some(state)
     }
-    println!("{}", horiz * depth);
 }
 
-fn parse(data: &str) -> Vec<Command> {
-    super::utils::parse(commands, data)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Submarine<M> {
+    model: M,
+    state: State,
+    /// The number of commands executed so far.
+    executed: usize,
 }
 
-fn commands(input: &str) -> IResult<&str, Vec<Command>> {
-    super::utils::lines(command)(input)
+impl<M: Model> Submarine<M> {
+    fn new(model: M) -> Self {
+        Self {
+            model,
+            state: State::default(),
+            executed: 0,
+        }
+    }
+
+    /// Runs `command`, leaving the submarine where it was if that would overflow.
+    fn execute(&mut self, command: Command) -> Result<State, Overflow> {
+        let index = self.executed;
+        self.state = self
+            .model
+            .apply(self.state, command)
+            .ok_or(Overflow { index, command })?;
+        self.executed += 1;
+        Ok(self.state)
+    }
+
+    /// The state after each of `commands`.
+    fn trajectory<I>(&mut self, commands: I) -> Result<Vec<State>, Overflow>
+    where
+        I: IntoIterator<Item = Command>,
+    {
+        commands
+            .into_iter()
+            .map(|command| self.execute(command))
+            .collect()
+    }
 }
 
+/// A command whose result doesn't fit in an `i64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Overflow {
+    /// How many commands ran before this one.
+    index: usize,
+    command: Command,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "command {} ({}) overflowed", self.index, self.command)
+    }
+}
+
+impl Error for Overflow {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Command {
     direction: Direction,
     distance: u64,
 }
 
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.direction {
+            Direction::Forward => "forward",
+            Direction::Back => "back",
+            Direction::Down => "down",
+            Direction::Up => "up",
+        };
+        write!(f, "{} {}", direction, self.distance)
+    }
+}
+
 fn command(input: &str) -> IResult<&str, Command> {
     use nom::{
         character::complete::{space1, u64 as u64_},
@@ -65,6 +207,7 @@ fn command(input: &str) -> IResult<&str, Command> {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
     Forward,
+    Back,
     Down,
     Up,
 }
@@ -74,7 +217,105 @@ fn direction(input: &str) -> IResult<&str, Direction> {
 
     alt((
         value(Direction::Forward, tag("forward")),
+        value(Direction::Back, tag("back")),
         value(Direction::Down, tag("down")),
         value(Direction::Up, tag("up")),
     ))(input)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2
+";
+
+    fn state(horizontal: i64, depth: i64, aim: i64) -> State {
+        State {
+            horizontal,
+            depth,
+            aim,
+        }
+    }
+
+    #[test]
+    fn example_trajectories_are_correct() {
+        let commands = parse(EXAMPLE);
+        let plain = Submarine::new(Plain).trajectory(commands.clone()).unwrap();
+        assert_eq!(Some(&state(15, 10, 0)), plain.last());
+
+        let aim = Submarine::new(Aim).trajectory(commands).unwrap();
+        let expected = vec![
+            state(5, 0, 0),
+            state(5, 0, 5),
+            state(13, 40, 5),
+            state(13, 40, 2),
+            state(13, 40, 10),
+            state(15, 60, 10),
+        ];
+        assert_eq!(expected, aim);
+    }
+
+    #[test]
+    fn back_undoes_forward() {
+        let commands = parse("down 3\nforward 7\nback 7\n");
+        let plain = Submarine::new(Plain).trajectory(commands.clone()).unwrap();
+        assert_eq!(state(0, 3, 0), plain[2]);
+
+        let aim = Submarine::new(Aim).trajectory(commands).unwrap();
+        assert_eq!(vec![state(0, 0, 3), state(7, 21, 3), state(0, 0, 3)], aim);
+    }
+
+    #[test]
+    fn surfacing_goes_negative() {
+        let commands = parse("up 4\nforward 2\n");
+        let plain = Submarine::new(Plain).trajectory(commands.clone()).unwrap();
+        assert_eq!(state(2, -4, 0), plain[1]);
+
+        let aim = Submarine::new(Aim).trajectory(commands).unwrap();
+        assert_eq!(state(2, -8, -4), aim[1]);
+    }
+
+    #[test]
+    fn overflow_is_reported() {
+        let commands = parse("down 9223372036854775807\nforward 2\nforward 1\n");
+        let mut submarine = Submarine::new(Aim);
+        let error = submarine.trajectory(commands.clone()).unwrap_err();
+        assert_eq!(
+            Overflow {
+                index: 1,
+                command: commands[1]
+            },
+            error
+        );
+        assert_eq!("command 1 (forward 2) overflowed", error.to_string());
+        assert_eq!(state(0, 0, i64::MAX), submarine.state);
+
+        let too_far = parse("down 9223372036854775808\n");
+        let error = Submarine::new(Plain).trajectory(too_far).unwrap_err();
+        assert_eq!(0, error.index);
+    }
+
+    #[test]
+    fn models_are_chosen_by_name() {
+        let args = |args: &[&str]| {
+            let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+            states(parse(EXAMPLE), &args).map_err(|err| err.to_string())
+        };
+        assert_eq!(Some(state(15, 60, 10)), args(&[]).unwrap().pop());
+        assert_eq!(Some(state(15, 10, 0)), args(&["plain"]).unwrap().pop());
+        assert_eq!(
+            Err("`deep` isn't a model, expected `plain` or `aim`".to_string()),
+            args(&["deep"])
+        );
+        let overflow = parse("down 9223372036854775807\nforward 2\n");
+        let err = states(overflow, &[]).unwrap_err();
+        assert_eq!("command 1 (forward 2) overflowed", err.to_string());
+    }
+}
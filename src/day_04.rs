@@ -1,122 +1,433 @@
-use super::utils::sep_array_5;
+use super::utils::{Vec2d, Vec2dBuilder};
 use nom::{
-    character::complete::{multispace1, u8 as u8_},
+    character::complete::{multispace1, u32 as u32_},
     multi::separated_list1,
     IResult,
 };
+use std::{collections::HashMap, error::Error, fmt};
 
-pub fn star_1(data: String) {
-    let (nums, bingos) = parse(&data);
-    let (i, score) = bingos
-        .iter()
-        .filter_map(|bingo| bingo.score(&nums[..]))
-        .min_by_key(|(i, _)| *i)
-        .unwrap();
-    println!("{}", score * u64::from(nums[i as usize]));
+const PUZZLE_RULES: [WinRule; 2] = [WinRule::Rows, WinRule::Columns];
+
+pub fn star_1(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    print_score(data, args, <[Winner]>::first)
 }
 
-pub fn star_2(data: String) {
+pub fn star_2(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    print_score(data, args, <[Winner]>::last)
+}
+
+/// Prints the score of the winner `pick` chooses. The rules can be given after the file name, as
+/// any of `rows`, `columns`, `diagonals`, `corners` and `full`, and are rows and columns by
+/// default.
+fn print_score(
+    data: String,
+    args: &[String],
+    pick: fn(&[Winner]) -> Option<&Winner>,
+) -> Result<(), Box<dyn Error>> {
+    let rules = rules(args)?;
     let (nums, bingos) = parse(&data);
-    let (i, score) = bingos
+    let winners = winners(&nums, &bingos, &rules);
+    let winner = pick(&winners).ok_or(NoWinner)?;
+    println!("{}", winner.score);
+    Ok(())
+}
+
+fn rules(args: &[String]) -> Result<Vec<WinRule>, UnknownRule> {
+    let rules = args
         .iter()
-        .filter_map(|bingo| bingo.score(&nums[..]))
-        .max_by_key(|(i, _)| *i)
-        .unwrap();
-    println!("{}", score * u64::from(nums[i as usize]));
+        .map(|arg| match arg.as_str() {
+            "rows" => Ok(WinRule::Rows),
+            "columns" => Ok(WinRule::Columns),
+            "diagonals" => Ok(WinRule::Diagonals),
+            "corners" => Ok(WinRule::Corners),
+            "full" => Ok(WinRule::FullCard),
+            _ => Err(UnknownRule(arg.clone())),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if rules.is_empty() {
+        Ok(PUZZLE_RULES.to_vec())
+    } else {
+        Ok(rules)
+    }
 }
 
-fn parse(input: &str) -> (Vec<u8>, Vec<Bingo>) {
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UnknownRule(String);
+
+impl fmt::Display for UnknownRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` isn't a win rule, expected `rows`, `columns`, `diagonals`, `corners` or `full`",
+            self.0
+        )
+    }
+}
+
+impl Error for UnknownRule {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NoWinner;
+
+impl fmt::Display for NoWinner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no board wins")
+    }
+}
+
+impl Error for NoWinner {}
+
+/// Plays every board until it wins, and returns the winners in the order they won. Boards that
+/// win on the same draw are ordered by their index.
+fn winners(numbers: &[u32], bingos: &[Bingo], rules: &[WinRule]) -> Vec<Winner> {
+    Simulator::new(bingos, rules)
+        .run(numbers.iter().copied())
+        .collect()
+}
+
+fn parse(input: &str) -> (Vec<u32>, Vec<Bingo>) {
     super::utils::parse(document, input)
 }
 
-fn document(input: &str) -> IResult<&str, (Vec<u8>, Vec<Bingo>)> {
+fn document(input: &str) -> IResult<&str, (Vec<u32>, Vec<Bingo>)> {
     use nom::sequence::separated_pair;
 
     separated_pair(num_list, multispace1, bingos)(input)
 }
 
-fn num_list(input: &str) -> IResult<&str, Vec<u8>> {
+fn num_list(input: &str) -> IResult<&str, Vec<u32>> {
     use nom::bytes::complete::tag;
 
-    separated_list1(tag(","), u8_)(input)
+    separated_list1(tag(","), u32_)(input)
 }
 
 fn bingos(input: &str) -> IResult<&str, Vec<Bingo>> {
     separated_list1(multispace1, bingo)(input)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Bingo([[u8; 5]; 5]);
+/// A way of completing a line on a board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum WinRule {
+    Rows,
+    Columns,
+    /// Both diagonals, on square boards only.
+    Diagonals,
+    Corners,
+    FullCard,
+}
+
+/// The cells that were all marked to win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Line {
+    Row(usize),
+    Column(usize),
+    /// From the top left to the bottom right.
+    Diagonal,
+    /// From the top right to the bottom left.
+    AntiDiagonal,
+    Corners,
+    FullCard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Winner {
+    /// The index of the board.
+    board: usize,
+    /// The index of the number that completed the line.
+    draw: usize,
+    line: Line,
+    /// The sum of the unmarked numbers, times the number that completed the line.
+    score: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bingo(Vec2d<u32>);
 
 impl Bingo {
-    fn score(&self, numbers: &[u8]) -> Option<(u8, u64)> {
-        let mut marked = [[false; 5]; 5];
-        let coords = numbers
-            .iter()
-            .enumerate()
-            .filter_map(|(i, number)| self.coords(*number).map(|c| (i, c)));
-
-        for (i, (x, y)) in coords {
-            marked[y][x] = true;
-            let horiz_win = marked[y].iter().all(|m| *m);
-            let vert_win = (0..5).map(|y| marked[y][x]).all(|m| m);
-            if horiz_win || vert_win {
-                let cells = self.0.iter().flatten();
-                let marks = marked.iter().flatten();
-                let sum = cells
-                    .zip(marks)
-                    .filter(|(_, mark)| !*mark)
-                    .map(|(cell, _)| u64::from(*cell))
-                    .sum();
-                return Some((i as u8, sum));
+    /// The lines that `rules` allow on this board.
+    fn lines(&self, rules: &[WinRule]) -> Vec<Line> {
+        let (width, height) = (self.0.width(), self.0.height());
+        let mut lines = Vec::new();
+        for rule in rules {
+            match rule {
+                WinRule::Rows => lines.extend((0..height).map(Line::Row)),
+                WinRule::Columns => lines.extend((0..width).map(Line::Column)),
+                WinRule::Diagonals if width == height => {
+                    lines.extend([Line::Diagonal, Line::AntiDiagonal])
+                }
+                WinRule::Diagonals => {}
+                WinRule::Corners => lines.push(Line::Corners),
+                WinRule::FullCard => lines.push(Line::FullCard),
             }
         }
-        None
+        lines
+    }
+
+    /// The coordinates of the cells in `line`.
+    fn cells(&self, line: Line) -> Vec<(usize, usize)> {
+        let (width, height) = (self.0.width(), self.0.height());
+        match line {
+            Line::Row(row) => (0..width).map(|col| (col, row)).collect(),
+            Line::Column(col) => (0..height).map(|row| (col, row)).collect(),
+            Line::Diagonal => (0..width).map(|i| (i, i)).collect(),
+            Line::AntiDiagonal => (0..width).map(|i| (width - 1 - i, i)).collect(),
+            Line::Corners => {
+                vec![
+                    (0, 0),
+                    (width - 1, 0),
+                    (0, height - 1),
+                    (width - 1, height - 1),
+                ]
+            }
+            Line::FullCard => self.0.all_coords().collect(),
+        }
     }
+}
+
+/// Plays many boards at once, marking each drawn number in constant time.
+///
+/// Each number is looked up in an index of the cells it appears in, and each line keeps a count
+/// of its cells that are still unmarked, so a line is complete as soon as its count hits zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Simulator {
+    /// Where each number appears, as indices into `cells`.
+    index: HashMap<u32, Vec<usize>>,
+    cells: Vec<Cell>,
+    lines: Vec<LineCount>,
+    /// The sum of the unmarked numbers on each board.
+    unmarked_sums: Vec<u64>,
+    won: Vec<bool>,
+    draws: usize,
+}
 
-    fn coords(&self, number: u8) -> Option<(usize, usize)> {
-        self.0
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cell {
+    board: usize,
+    number: u32,
+    marked: bool,
+    /// The lines through this cell, as indices into `Simulator::lines`.
+    lines: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LineCount {
+    line: Line,
+    unmarked: usize,
+}
+
+impl Simulator {
+    fn new(bingos: &[Bingo], rules: &[WinRule]) -> Self {
+        let mut index = HashMap::<_, Vec<_>>::new();
+        let mut cells = Vec::new();
+        let mut lines = Vec::new();
+        for (board, bingo) in bingos.iter().enumerate() {
+            let first_cell = cells.len();
+            let cell_idx = |(col, row)| first_cell + row * bingo.0.width() + col;
+            for coord in bingo.0.all_coords() {
+                let number = bingo.0[coord];
+                index.entry(number).or_default().push(cell_idx(coord));
+                cells.push(Cell {
+                    board,
+                    number,
+                    marked: false,
+                    lines: Vec::new(),
+                });
+            }
+            for line in bingo.lines(rules) {
+                let coords = bingo.cells(line);
+                for coord in &coords {
+                    cells[cell_idx(*coord)].lines.push(lines.len());
+                }
+                lines.push(LineCount {
+                    line,
+                    unmarked: coords.len(),
+                });
+            }
+        }
+
+        let unmarked_sums = bingos
             .iter()
-            .enumerate()
-            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, cell)| ((x, y), cell)))
-            .find(|(_, cell)| **cell == number)
-            .map(|(c, _)| c)
+            .map(|b| b.0.iter().map(|n| u64::from(*n)).sum())
+            .collect();
+        Self {
+            index,
+            cells,
+            lines,
+            unmarked_sums,
+            won: vec![false; bingos.len()],
+            draws: 0,
+        }
+    }
+
+    /// Marks `number` everywhere it appears, and returns the boards that won because of it, in
+    /// order. Boards that have already won don't win again.
+    fn draw(&mut self, number: u32) -> Vec<Winner> {
+        let draw = self.draws;
+        self.draws += 1;
+
+        // The first line each board completed, in the order the rules list them.
+        let mut completed = Vec::<(usize, usize)>::new();
+        for &cell_idx in self.index.get(&number).into_iter().flatten() {
+            let cell = &mut self.cells[cell_idx];
+            if cell.marked {
+                continue;
+            }
+            cell.marked = true;
+            self.unmarked_sums[cell.board] -= u64::from(cell.number);
+            for &line_idx in &cell.lines {
+                self.lines[line_idx].unmarked -= 1;
+                if self.lines[line_idx].unmarked == 0 && !self.won[cell.board] {
+                    match completed.iter_mut().find(|(board, _)| *board == cell.board) {
+                        Some((_, first)) => *first = (*first).min(line_idx),
+                        None => completed.push((cell.board, line_idx)),
+                    }
+                }
+            }
+        }
+
+        completed.sort_unstable();
+        completed
+            .into_iter()
+            .map(|(board, line_idx)| {
+                self.won[board] = true;
+                Winner {
+                    board,
+                    draw,
+                    line: self.lines[line_idx].line,
+                    score: self.unmarked_sums[board] * u64::from(number),
+                }
+            })
+            .collect()
+    }
+
+    /// Draws each of the numbers in turn, yielding boards as they win.
+    fn run<I>(mut self, numbers: I) -> impl Iterator<Item = Winner>
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        numbers
+            .into_iter()
+            .flat_map(move |number| self.draw(number))
     }
 }
 
 fn bingo(input: &str) -> IResult<&str, Bingo> {
-    use nom::{character::complete::line_ending, combinator::map};
+    use nom::{character::complete::line_ending, combinator::map_opt};
 
-    map(sep_array_5(line_ending, bingo_row), Bingo)(input)
+    map_opt(separated_list1(line_ending, bingo_row), |rows| {
+        let width = rows[0].len();
+        if rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+        let mut builder = Vec2dBuilder::new();
+        for row in rows {
+            row.into_iter().for_each(|cell| builder.push(cell));
+            builder.finish_row();
+        }
+        Some(Bingo(builder.build()))
+    })(input)
 }
 
-fn bingo_row(input: &str) -> IResult<&str, [u8; 5]> {
+fn bingo_row(input: &str) -> IResult<&str, Vec<u32>> {
     use nom::{
         character::complete::{space0, space1},
         sequence::preceded,
     };
 
-    preceded(space0, sep_array_5(space1, u8_))(input)
+    preceded(space0, separated_list1(space1, u32_))(input)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = "\
+7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7";
+
+    /// Plays a single board by rescanning it after every draw.
+    fn first_win(
+        bingo: &Bingo,
+        numbers: &[u32],
+        rules: &[WinRule],
+        board: usize,
+    ) -> Option<Winner> {
+        let lines = bingo.lines(rules);
+        let mut marked = Vec2d::repeat(false, bingo.0.width(), bingo.0.height());
+        for (draw, number) in numbers.iter().enumerate() {
+            for coord in bingo.0.all_coords().filter(|c| bingo.0[*c] == *number) {
+                marked[coord] = true;
+            }
+            let line = lines
+                .iter()
+                .find(|line| bingo.cells(**line).iter().all(|c| marked[*c]));
+            if let Some(line) = line {
+                let sum = bingo
+                    .0
+                    .all_coords()
+                    .filter(|c| !marked[*c])
+                    .map(|c| u64::from(bingo.0[c]))
+                    .sum::<u64>();
+                return Some(Winner {
+                    board,
+                    draw,
+                    line: *line,
+                    score: sum * u64::from(*number),
+                });
+            }
+        }
+        None
+    }
+
+    fn board(rows: &[&[u32]]) -> Bingo {
+        let mut builder = Vec2dBuilder::new();
+        for row in rows {
+            row.iter().for_each(|cell| builder.push(*cell));
+            builder.finish_row();
+        }
+        Bingo(builder.build())
+    }
+
     #[test]
     fn score_is_correct() {
         let nums = [
             7, 4, 9, 5, 11, 17, 23, 2, 0, 14, 21, 24, 10, 16, 13, 6, 15, 25, 12, 22, 18, 20, 8, 19,
             3, 26, 1,
         ];
-        let bingo = Bingo([
-            [14, 21, 17, 24, 4],
-            [10, 16, 15, 9, 19],
-            [18, 8, 23, 26, 20],
-            [22, 11, 13, 6, 5],
-            [2, 0, 12, 3, 7],
+        let bingo = board(&[
+            &[14, 21, 17, 24, 4],
+            &[10, 16, 15, 9, 19],
+            &[18, 8, 23, 26, 20],
+            &[22, 11, 13, 6, 5],
+            &[2, 0, 12, 3, 7],
         ]);
-        assert_eq!(Some((11, 188)), bingo.score(&nums[..]));
+        let expected = Winner {
+            board: 0,
+            draw: 11,
+            line: Line::Row(0),
+            score: 188 * 24,
+        };
+        assert_eq!(
+            Some(expected),
+            first_win(&bingo, &nums[..], &PUZZLE_RULES, 0)
+        );
     }
 
     #[test]
@@ -130,14 +441,156 @@ mod tests {
         );
         let expected = Ok((
             "\n",
-            Bingo([
-                [22, 13, 17, 11, 0],
-                [8, 2, 23, 4, 24],
-                [21, 9, 14, 16, 7],
-                [6, 10, 3, 18, 5],
-                [1, 12, 20, 15, 19],
+            board(&[
+                &[22, 13, 17, 11, 0],
+                &[8, 2, 23, 4, 24],
+                &[21, 9, 14, 16, 7],
+                &[6, 10, 3, 18, 5],
+                &[1, 12, 20, 15, 19],
             ]),
         ));
         assert_eq!(expected, bingo(input));
     }
+
+    #[test]
+    fn any_size_boards_parse() {
+        let (nums, bingos) = parse("100000,2\n\n1 2 3\n4 5 100000\n\n7\n8");
+        assert_eq!(vec![100000, 2], nums);
+        assert_eq!(
+            vec![board(&[&[1, 2, 3], &[4, 5, 100000]]), board(&[&[7], &[8]])],
+            bingos
+        );
+        assert!(bingo("1 2\n3").is_err());
+    }
+
+    #[test]
+    fn example_winners_are_ordered() {
+        let (nums, bingos) = parse(EXAMPLE);
+        let winners = winners(&nums, &bingos, &PUZZLE_RULES);
+        let order = winners
+            .iter()
+            .map(|w| (w.board, w.draw))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(2, 11), (0, 13), (1, 14)], order);
+        assert_eq!(4512, winners[0].score);
+        assert_eq!(1924, winners[2].score);
+    }
+
+    #[test]
+    fn win_rules_are_selectable() {
+        let bingo = board(&[&[1, 2, 3], &[4, 5, 6], &[7, 8, 9]]);
+        let first_win = |nums: &[u32], rules: &[WinRule]| {
+            winners(nums, std::slice::from_ref(&bingo), rules)
+                .first()
+                .map(|w| (w.draw, w.line, w.score))
+        };
+
+        let nums = [1, 5, 3, 9, 7];
+        assert_eq!(None, first_win(&nums, &PUZZLE_RULES));
+        assert_eq!(
+            Some((3, Line::Diagonal, (2 + 4 + 6 + 7 + 8) * 9)),
+            first_win(&nums, &[WinRule::Diagonals])
+        );
+        assert_eq!(
+            Some((4, Line::Corners, (2 + 4 + 6 + 8) * 7)),
+            first_win(&nums, &[WinRule::Corners])
+        );
+        assert_eq!(None, first_win(&nums, &[WinRule::FullCard]));
+        let all = (1..=9).rev().collect::<Vec<_>>();
+        assert_eq!(
+            Some((8, Line::FullCard, 0)),
+            first_win(&all, &[WinRule::FullCard])
+        );
+        assert_eq!(
+            Some((2, Line::AntiDiagonal, (1 + 2 + 4 + 6 + 8 + 9) * 3)),
+            first_win(&[7, 5, 3], &[WinRule::Rows, WinRule::Diagonals])
+        );
+
+        let wide = board(&[&[1, 2, 3], &[4, 5, 6]]);
+        assert!(wide.lines(&[WinRule::Diagonals]).is_empty());
+        assert_eq!(
+            Some(Line::Column(2)),
+            winners(&[3, 6], &[wide], &[WinRule::Columns])
+                .first()
+                .map(|w| w.line)
+        );
+    }
+
+    #[test]
+    fn simulator_matches_rescanning() {
+        let mut rng = crate::utils::Rng::new(12345);
+        let mut next = move |bound| rng.below(bound);
+
+        let bingos = (0..200)
+            .map(|_| {
+                let width = next(4) as usize + 2;
+                let height = if next(2) == 0 {
+                    width
+                } else {
+                    next(4) as usize + 2
+                };
+                let mut builder = Vec2dBuilder::new();
+                for _ in 0..height {
+                    (0..width).for_each(|_| builder.push(next(60) as u32));
+                    builder.finish_row();
+                }
+                Bingo(builder.build())
+            })
+            .collect::<Vec<_>>();
+        let numbers = (0..300).map(|_| next(60) as u32).collect::<Vec<_>>();
+
+        let rule_sets: [&[WinRule]; 4] = [
+            &PUZZLE_RULES,
+            &[WinRule::Diagonals, WinRule::Corners],
+            &[WinRule::FullCard],
+            &[
+                WinRule::Corners,
+                WinRule::Rows,
+                WinRule::Diagonals,
+                WinRule::Columns,
+            ],
+        ];
+        for rules in rule_sets {
+            let mut expected = bingos
+                .iter()
+                .enumerate()
+                .filter_map(|(board, bingo)| first_win(bingo, &numbers, rules, board))
+                .collect::<Vec<_>>();
+            expected.sort_by_key(|w| (w.draw, w.board));
+            assert_eq!(expected, winners(&numbers, &bingos, rules));
+        }
+    }
+
+    #[test]
+    fn draws_are_streamed() {
+        let (nums, bingos) = parse(EXAMPLE);
+        let mut simulator = Simulator::new(&bingos, &PUZZLE_RULES);
+        for number in &nums[..11] {
+            assert!(simulator.draw(*number).is_empty());
+        }
+        let winners = simulator.draw(nums[11]);
+        assert_eq!(1, winners.len());
+        assert_eq!(
+            (2, Line::Row(0), 4512),
+            (winners[0].board, winners[0].line, winners[0].score)
+        );
+
+        // Winners stay won, even if another of their lines is completed.
+        let mut rest = simulator.run(nums[12..].iter().copied());
+        assert_eq!(Some((0, 13)), rest.next().map(|w| (w.board, w.draw)));
+        assert_eq!(Some((1, 14)), rest.next().map(|w| (w.board, w.draw)));
+        assert_eq!(None, rest.next());
+    }
+
+    #[test]
+    fn rules_are_parsed() {
+        let args =
+            |args: &[&str]| rules(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>());
+        assert_eq!(Ok(PUZZLE_RULES.to_vec()), args(&[]));
+        assert_eq!(
+            Ok(vec![WinRule::Corners, WinRule::FullCard]),
+            args(&["corners", "full"])
+        );
+        assert_eq!(Err(UnknownRule("x".to_string())), args(&["rows", "x"]));
+    }
 }
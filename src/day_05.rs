@@ -1,32 +1,203 @@
+use super::utils::{BoundingBox, IntervalSet, Point};
 use nom::{bytes::complete::tag, combinator::map, sequence::separated_pair, IResult};
-use std::{collections::HashMap, iter::IntoIterator};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+    io::{self, Write},
+    iter::IntoIterator,
+};
 
-pub fn star_1(data: String) {
+type Coord = Point<i64, 2>;
+
+/// The largest coordinate a line can have. Lines are traced and intersected with sums and doubled
+/// differences of coordinates, which stay in range below this.
+const MAX_COORD: i64 = i64::MAX / 4;
+
+pub fn star_1(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let lines = parse(&data);
+    let lines = lines
+        .into_iter()
+        .filter(|line| !line.is_diagonal())
+        .collect();
+    print_overlaps(lines, args)
+}
+
+pub fn star_2(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
     let lines = parse(&data);
-    let iter = lines.into_iter().filter(|line| !line.is_diagonal());
-    let intersections = intersections(iter);
-    println!("{}", intersections);
+    print_overlaps(lines, args)
+}
+
+/// Prints how many points are covered by at least as many lines as the threshold given after the
+/// file name, which is 2 by default.
+fn print_overlaps(lines: Vec<Line>, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let threshold = threshold(args)?;
+    println!("{}", count_overlaps(lines, threshold));
+    Ok(())
 }
 
-pub fn star_2(data: String) {
+fn threshold(args: &[String]) -> Result<usize, InvalidThreshold> {
+    match args.first() {
+        Some(arg) => match arg.parse() {
+            Ok(threshold) if threshold > 0 => Ok(threshold),
+            _ => Err(InvalidThreshold(arg.clone())),
+        },
+        None => Ok(2),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InvalidThreshold(String);
+
+impl fmt::Display for InvalidThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` isn't a positive number of lines", self.0)
+    }
+}
+
+impl Error for InvalidThreshold {}
+
+/// Prints how many lines cover each point, in the same style as the puzzle's diagrams.
+pub fn diagram(data: String) {
     let lines = parse(&data);
-    let iter = lines.into_iter();
-    let intersections = intersections(iter);
-    println!("{}", intersections);
+    if let Some(density) = density(&lines) {
+        render(&density, io::stdout().lock()).unwrap();
+    }
+}
+
+/// The number of points covered by at least `threshold` lines. For a threshold of 2, lines are
+/// intersected analytically when they're all horizontal, vertical or at 45°. Otherwise they're
+/// rasterised.
+fn count_overlaps(lines: Vec<Line>, threshold: usize) -> usize {
+    if threshold == 2 && lines.iter().all(|line| line.segment().is_some()) {
+        intersections(lines.into_iter())
+    } else {
+        overlaps(&coverage(&lines), threshold)
+    }
 }
 
+/// How many lines cover each point that's on any of them. Only those points are stored, so lines
+/// far from the origin or from each other are as cheap as any others of the same length.
+fn coverage(lines: &[Line]) -> HashMap<Coord, usize> {
+    let mut coverage = HashMap::new();
+    for coord in lines.iter().flat_map(|line| line.all_coords()) {
+        *coverage.entry(coord).or_insert(0) += 1;
+    }
+    coverage
+}
+
+/// The number of points covered by at least `threshold` lines.
+fn overlaps(coverage: &HashMap<Coord, usize>, threshold: usize) -> usize {
+    coverage.values().filter(|&&n| n >= threshold).count()
+}
+
+/// How many lines cover each point, along with the smallest box around the lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Density {
+    bounds: BoundingBox<i64, 2>,
+    coverage: HashMap<Coord, usize>,
+}
+
+/// The density of the lines, or `None` if there are no lines.
+fn density(lines: &[Line]) -> Option<Density> {
+    let ends = lines.iter().flat_map(|line| [line.start, line.end]);
+    Some(Density {
+        bounds: BoundingBox::around(ends)?,
+        coverage: coverage(lines),
+    })
+}
+
+/// Draws each point in the box as `.` if no lines cover it, and as the number of lines otherwise.
+/// Counts above 9 are drawn as `+`. The top left corner of the drawing is the top left corner of
+/// the box.
+fn render<W: Write>(density: &Density, mut out: W) -> io::Result<()> {
+    let BoundingBox { min, max } = density.bounds;
+    for y in min.y()..=max.y() {
+        let row = (min.x()..=max.x()).map(|x| {
+            match density
+                .coverage
+                .get(&Coord::new(x, y))
+                .copied()
+                .unwrap_or(0)
+            {
+                0 => '.',
+                n @ 1..=9 => char::from_digit(n as u32, 10).unwrap(),
+                _ => '+',
+            }
+        });
+        writeln!(out, "{}", row.collect::<String>())?;
+    }
+    Ok(())
+}
+
+/// The number of points covered by at least two lines.
+///
+/// Rather than enumerating every point on every line, this intersects each pair of lines, so the
+/// cost doesn't depend on how long the lines are.
+///
+/// Panics if a line isn't horizontal, vertical or at 45°.
 fn intersections<I>(lines: I) -> usize
 where
     I: Iterator<Item = Line>,
 {
-    let all_coords = lines.flat_map(Line::all_coords);
+    let segments = lines
+        .map(|line| {
+            line.segment()
+                .expect("only horizontal, vertical and 45° lines are supported")
+        })
+        .collect::<Vec<_>>();
 
-    let mut coord_map = HashMap::new();
-    for coord in all_coords {
-        *coord_map.entry(coord).or_insert(0) += 1;
+    // Collinear lines share an interval of their carrier, and other lines share at most a point.
+    let mut shared = HashMap::<Carrier, IntervalSet>::new();
+    let mut crossings = HashSet::new();
+    for (i, a) in segments.iter().enumerate() {
+        for b in &segments[i + 1..] {
+            if a.carrier == b.carrier {
+                let (min, max) = (a.min.max(b.min), a.max.min(b.max));
+                if min <= max {
+                    shared.entry(a.carrier).or_default().insert(min..=max);
+                }
+            } else if let Some(point) = a.carrier.crossing(b.carrier) {
+                if a.contains(point) && b.contains(point) {
+                    crossings.insert(point);
+                }
+            }
+        }
     }
 
-    coord_map.values().filter(|n| **n > 1).count()
+    // The number of shared intervals containing `point`. Only one carrier in each direction goes
+    // through it, so this is at most 4.
+    let coverage = |point| {
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| {
+                let carrier = Carrier::through(direction, point);
+                shared
+                    .get(&carrier)
+                    .is_some_and(|set| set.contains(direction.position(point)))
+            })
+            .count()
+    };
+
+    // Shared intervals on different carriers can meet, in which case the point where they meet
+    // has been counted once for each of them.
+    let carriers = shared.keys().copied().collect::<Vec<_>>();
+    let mut meetings = HashSet::new();
+    for (i, a) in carriers.iter().enumerate() {
+        for b in &carriers[i + 1..] {
+            if let Some(point) = a.crossing(*b) {
+                if coverage(point) > 1 {
+                    meetings.insert(point);
+                }
+            }
+        }
+    }
+
+    let mut count = shared.values().map(|set| set.len() as usize).sum::<usize>();
+    for point in meetings {
+        count -= coverage(point) - 1;
+    }
+    count + crossings.into_iter().filter(|&p| coverage(p) == 0).count()
 }
 
 fn parse(input: &str) -> Vec<Line> {
@@ -45,46 +216,162 @@ struct Line {
 
 impl Line {
     fn is_diagonal(self) -> bool {
-        (self.start.x != self.end.x) && (self.start.y != self.end.y)
+        // The distances only agree when a single coordinate changes.
+        self.start.manhattan_distance(self.end) != self.start.chebyshev_distance(self.end)
     }
 
+    /// The part of its carrier the line covers, or `None` if it isn't horizontal, vertical or at
+    /// 45°.
+    fn segment(self) -> Option<Segment> {
+        let (start, end) = self.ends();
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+
+        let direction = if dy == 0 {
+            Direction::Horizontal
+        } else if dx == 0 {
+            Direction::Vertical
+        } else if dx == dy {
+            Direction::Diagonal
+        } else if dx == -dy {
+            Direction::AntiDiagonal
+        } else {
+            return None;
+        };
+
+        let (a, b) = (direction.position(start), direction.position(end));
+        Some(Segment {
+            carrier: Carrier::through(direction, start),
+            min: a.min(b),
+            max: a.max(b),
+        })
+    }
+
+    fn ends(self) -> ((i64, i64), (i64, i64)) {
+        let pair = |coord: Coord| (coord.x(), coord.y());
+        (pair(self.start), pair(self.end))
+    }
+
+    /// Every point on the line, rasterised with Bresenham's algorithm so any slope works.
+    ///
+    /// Lines are always traced from the lesser end, so a line covers the same points whichever
+    /// way round it's given.
     fn all_coords(self) -> impl Iterator<Item = Coord> {
-        use std::{iter, ops::RangeInclusive};
+        let (start, end) = self.ends();
+        let (start, end) = (start.min(end), start.max(end));
+        let (dx, dy) = (end.0 - start.0, -(end.1 - start.1).abs());
+        let step_y = if end.1 < start.1 { -1 } else { 1 };
+
+        let mut next = Some(start);
+        let mut error = dx + dy;
+        std::iter::from_fn(move || {
+            let (x, y) = next?;
+            next = if (x, y) == end {
+                None
+            } else {
+                let (mut x, mut y) = (x, y);
+                let doubled = 2 * error;
+                if doubled >= dy {
+                    error += dy;
+                    x += 1;
+                }
+                if doubled <= dx {
+                    error += dx;
+                    y += step_y;
+                }
+                Some((x, y))
+            };
+            Some(Coord::new(x, y))
+        })
+    }
+}
+
+/// The directions [`intersections`] can handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Direction {
+    Horizontal,
+    Vertical,
+    /// Both coordinates increase together.
+    Diagonal,
+    /// One coordinate increases as the other decreases.
+    AntiDiagonal,
+}
+
+impl Direction {
+    const ALL: [Self; 4] = [
+        Self::Horizontal,
+        Self::Vertical,
+        Self::Diagonal,
+        Self::AntiDiagonal,
+    ];
+
+    /// Coefficients `(a, b)` such that `a * x + b * y` is the same for every point on a line in
+    /// this direction.
+    fn normal(self) -> (i64, i64) {
+        match self {
+            Self::Horizontal => (0, 1),
+            Self::Vertical => (1, 0),
+            Self::Diagonal => (1, -1),
+            Self::AntiDiagonal => (1, 1),
+        }
+    }
 
-        #[derive(Debug, Clone)]
-        enum Iter {
-            Less(RangeInclusive<u64>),
-            Equal(iter::Repeat<u64>),
-            Greater(iter::Rev<RangeInclusive<u64>>),
+    /// How far along a line in this direction a point is.
+    fn position(self, (x, y): (i64, i64)) -> i64 {
+        match self {
+            Self::Vertical => y,
+            _ => x,
         }
+    }
+}
 
-        impl Iter {
-            fn new(a: u64, b: u64) -> Self {
-                use std::cmp::Ordering;
+/// An infinite line that segments lie on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Carrier {
+    direction: Direction,
+    /// `a * x + b * y` for the direction's normal `(a, b)`.
+    offset: i64,
+}
 
-                match a.cmp(&b) {
-                    Ordering::Less => Self::Less(a..=b),
-                    Ordering::Equal => Self::Equal(std::iter::repeat(a)),
-                    Ordering::Greater => Self::Greater((b..=a).rev()),
-                }
-            }
+impl Carrier {
+    fn through(direction: Direction, (x, y): (i64, i64)) -> Self {
+        let (a, b) = direction.normal();
+        Self {
+            direction,
+            offset: a * x + b * y,
         }
+    }
 
-        impl Iterator for Iter {
-            type Item = u64;
+    /// The grid point where two carriers cross, if they aren't parallel and cross on one.
+    fn crossing(self, other: Self) -> Option<(i64, i64)> {
+        let (a1, b1) = self.direction.normal();
+        let (a2, b2) = other.direction.normal();
+        let det = a1 * b2 - a2 * b1;
+        if det == 0 {
+            return None;
+        }
 
-            fn next(&mut self) -> Option<Self::Item> {
-                match self {
-                    Self::Less(i) => i.next(),
-                    Self::Equal(i) => i.next(),
-                    Self::Greater(i) => i.next(),
-                }
-            }
+        let x = self.offset * b2 - other.offset * b1;
+        let y = a1 * other.offset - a2 * self.offset;
+        if x % det != 0 || y % det != 0 {
+            return None;
         }
+        Some((x / det, y / det))
+    }
+}
+
+/// The positions from `min` to `max` along a carrier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    carrier: Carrier,
+    min: i64,
+    max: i64,
+}
 
-        let range_x = Iter::new(self.start.x, self.end.x);
-        let range_y = Iter::new(self.start.y, self.end.y);
-        range_x.zip(range_y).map(|(x, y)| Coord { x, y })
+impl Segment {
+    fn contains(self, point: (i64, i64)) -> bool {
+        let direction = self.carrier.direction;
+        Carrier::through(direction, point) == self.carrier
+            && (self.min..=self.max).contains(&direction.position(point))
     }
 }
 
@@ -99,17 +386,208 @@ fn line(input: &str) -> IResult<&str, Line> {
     })(input)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Coord {
-    x: u64,
-    y: u64,
+fn coord(input: &str) -> IResult<&str, Coord> {
+    use nom::{character::complete::u64 as u64_, combinator::map_opt};
+
+    let value = || map_opt(u64_, |n| i64::try_from(n).ok().filter(|&n| n <= MAX_COORD));
+    map(separated_pair(value(), tag(","), value()), Coord::from)(input)
 }
 
-fn coord(input: &str) -> IResult<&str, Coord> {
-    use nom::character::complete::u64 as u64_;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    map(separated_pair(u64_, tag(","), u64_), |(x, y)| Coord {
-        x,
-        y,
-    })(input)
+    const EXAMPLE: &str = "\
+0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2
+";
+
+    /// Counts overlaps by enumerating every point on every line.
+    fn enumerated(lines: &[Line]) -> usize {
+        let mut coord_map = HashMap::new();
+        for coord in lines.iter().flat_map(|line| line.all_coords()) {
+            *coord_map.entry(coord).or_insert(0) += 1;
+        }
+        coord_map.values().filter(|n| **n > 1).count()
+    }
+
+    fn line(start: (i64, i64), end: (i64, i64)) -> Line {
+        Line {
+            start: start.into(),
+            end: end.into(),
+        }
+    }
+
+    fn rendered(lines: &[Line]) -> String {
+        let mut out = Vec::new();
+        render(&density(lines).unwrap(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn example_overlaps_are_correct() {
+        let lines = parse(EXAMPLE);
+        let straight = lines.iter().copied().filter(|line| !line.is_diagonal());
+        assert_eq!(5, intersections(straight));
+        assert_eq!(12, intersections(lines.into_iter()));
+    }
+
+    #[test]
+    fn example_diagram_is_correct() {
+        let expected = "\
+1.1....11.
+.111...2..
+..2.1.111.
+...1.2.2..
+.112313211
+...1.2....
+..1...1...
+.1.....1..
+1.......1.
+222111....
+";
+        assert_eq!(expected, rendered(&parse(EXAMPLE)));
+
+        let lines = parse(EXAMPLE);
+        assert_eq!(12, count_overlaps(lines.clone(), 2));
+        assert_eq!(2, count_overlaps(lines.clone(), 3));
+        assert_eq!(0, count_overlaps(lines.clone(), 4));
+        assert_eq!(39, count_overlaps(lines, 1));
+    }
+
+    #[test]
+    fn diagrams_start_at_the_nearest_line() {
+        let n = 1 << 40;
+        let lines = [
+            line((n, n + 1), (n + 2, n + 1)),
+            line((n + 1, n), (n + 1, n + 2)),
+        ];
+        let expected = "\
+.1.
+121
+.1.
+";
+        assert_eq!(expected, rendered(&lines));
+        assert_eq!(5, density(&lines).unwrap().coverage.len());
+    }
+
+    #[test]
+    fn far_lines_of_any_slope_are_cheap() {
+        let n = 1 << 40;
+        let lines = vec![
+            line((n, n), (n + 4, n + 2)),
+            line((n, n + 1), (n + 4, n + 1)),
+        ];
+        assert_eq!(2, count_overlaps(lines.clone(), 2));
+        assert_eq!(8, count_overlaps(lines, 1));
+    }
+
+    #[test]
+    fn coordinates_must_leave_room_to_trace() {
+        let far = MAX_COORD.to_string();
+        assert!(super::line(&format!("0,{} -> {},0", far, far)).is_ok());
+        let too_far = (MAX_COORD + 1).to_string();
+        assert!(super::line(&format!("0,{} -> 0,0", too_far)).is_err());
+        assert!(super::line("0,18446744073709551615 -> 0,0").is_err());
+    }
+
+    #[test]
+    fn thresholds_must_be_positive() {
+        let args =
+            |args: &[&str]| threshold(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>());
+        assert_eq!(Ok(2), args(&[]));
+        assert_eq!(Ok(3), args(&["3"]));
+        let err = args(&["0"]).unwrap_err();
+        assert_eq!("`0` isn't a positive number of lines", err.to_string());
+        assert!(args(&["two"]).is_err());
+    }
+
+    #[test]
+    fn lines_can_have_any_slope() {
+        let shallow = line((0, 0), (4, 2));
+        let coords = shallow
+            .all_coords()
+            .map(|c| (c.x(), c.y()))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)], coords);
+
+        let steep = line((3, 0), (0, 7));
+        let coords = steep.all_coords().collect::<Vec<_>>();
+        assert_eq!(8, coords.len());
+        assert_eq!((Coord::new(0, 7), Coord::new(3, 0)), (coords[0], coords[7]));
+        for pair in coords.windows(2) {
+            assert_eq!(1, pair[0].chebyshev_distance(pair[1]));
+        }
+
+        let reversed = line((0, 7), (3, 0)).all_coords().collect::<Vec<_>>();
+        assert_eq!(coords, reversed);
+
+        let lines = vec![shallow, line((0, 1), (4, 1))];
+        assert_eq!(2, count_overlaps(lines, 2));
+    }
+
+    #[test]
+    fn diagonals_cross_between_grid_points() {
+        let lines = [line((0, 0), (3, 3)), line((0, 1), (1, 0))];
+        assert_eq!(0, intersections(lines.into_iter()));
+        let lines = [line((0, 0), (3, 3)), line((0, 2), (2, 0))];
+        assert_eq!(1, intersections(lines.into_iter()));
+    }
+
+    #[test]
+    fn single_points_are_lines() {
+        let point = line((2, 2), (2, 2));
+        let lines = [point, line((0, 0), (4, 4)), line((4, 0), (0, 4))];
+        assert_eq!(1, intersections(lines.into_iter()));
+        assert_eq!(1, intersections([point, point].into_iter()));
+        assert_eq!(0, intersections([point, line((0, 3), (4, 3))].into_iter()));
+    }
+
+    #[test]
+    fn long_lines_are_cheap() {
+        let n = 1 << 40;
+        let lines = [
+            line((0, 0), (n, n)),
+            line((0, n), (n, 0)),
+            line((0, n / 2), (n, n / 2)),
+            line((n / 4, n / 4), (n, n)),
+        ];
+        assert_eq!(n - n / 4 + 1, intersections(lines.into_iter()) as i64);
+    }
+
+    #[test]
+    fn matches_enumeration() {
+        let mut rng = crate::utils::Rng::new(0x2545_f491_4f6c_dd1d);
+        let mut next = |bound| rng.below(bound) as i64;
+
+        for _ in 0..200 {
+            let lines = (0..next(15) + 1)
+                .map(|_| {
+                    let (x, y, len) = (next(12), next(12), next(12));
+                    let (start, end) = match next(4) {
+                        0 => ((x, y), (x + len, y)),
+                        1 => ((x, y), (x, y + len)),
+                        2 => ((x, y), (x + len, y + len)),
+                        _ => ((x, y + len), (x + len, y)),
+                    };
+                    if next(2) == 0 {
+                        line(start, end)
+                    } else {
+                        line(end, start)
+                    }
+                })
+                .collect::<Vec<_>>();
+            let expected = enumerated(&lines);
+            assert_eq!(expected, intersections(lines.iter().copied()));
+            assert_eq!(expected, overlaps(&coverage(&lines), 2));
+        }
+    }
 }
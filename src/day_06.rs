@@ -1,82 +1,356 @@
+use super::utils::Simulation;
 use nom::IResult;
+use num::{
+    traits::{CheckedAdd, CheckedMul},
+    BigUint, One, ToPrimitive, Zero,
+};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Write},
+    ops::RangeInclusive,
+};
 
-pub fn star_1(data: String) {
-    print_fish_count(data, 80);
+pub fn star_1(data: String) -> Result<(), Box<dyn Error>> {
+    print_fish_count(data, 80)
 }
 
-pub fn star_2(data: String) {
-    print_fish_count(data, 256);
+pub fn star_2(data: String) -> Result<(), Box<dyn Error>> {
+    print_fish_count(data, 256)
 }
 
-fn print_fish_count(data: String, days: u64) {
-    let fish = parse(&data);
-    let fish = Fish::new(fish);
-    let fish = fish.evolve(days);
-    println!("{}", fish.count());
+/// Prints the population on each day as CSV.
+///
+/// The first and last days can be given as two more arguments after the input file, and default
+/// to the days star 2 covers.
+pub fn trajectory(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let days = days(args)?;
+    let fish = Fish::new(Lifecycle::default(), parse(&data))?;
+    let samples = fish.trajectory(days)?;
+    write_csv(&samples, io::stdout().lock())?;
+    Ok(())
+}
+
+/// Parses the optional first and last days for `trajectory`.
+fn days(args: &[String]) -> Result<RangeInclusive<u64>, DaysError> {
+    let mut args = args.iter();
+    let mut day = |default| match args.next() {
+        Some(arg) => arg.parse().map_err(|_| DaysError::Invalid(arg.clone())),
+        None => Ok(default),
+    };
+    let first = day(0)?;
+    let last = day(256)?;
+    if last < first {
+        return Err(DaysError::Reversed { first, last });
+    }
+    Ok(first..=last)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DaysError {
+    Invalid(String),
+    Reversed { first: u64, last: u64 },
+}
+
+impl fmt::Display for DaysError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(arg) => write!(f, "`{}` isn't a day", arg),
+            Self::Reversed { first, last } => {
+                write!(f, "last day {} is before first day {}", last, first)
+            }
+        }
+    }
+}
+
+impl Error for DaysError {}
+
+fn print_fish_count(data: String, days: u64) -> Result<(), Box<dyn Error>> {
+    let fish = Fish::new(Lifecycle::default(), parse(&data))?;
+    println!("{}", fish.count_after(days));
+    Ok(())
 }
 
 fn parse(input: &str) -> Vec<u64> {
     super::utils::parse(fish, input)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Fish([u64; 9]);
+/// How often lanternfish spawn.
+///
+/// A fish with timer 0 spawns a new fish and resets to `spawn_period - 1`. Newborn fish start at
+/// `spawn_period + newborn_delay - 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Lifecycle {
+    spawn_period: usize,
+    newborn_delay: usize,
+}
 
-impl Fish {
-    fn new(fish: Vec<u64>) -> Self {
-        let mut arr = [0; 9];
-        for f in fish {
-            arr[f as usize] += 1;
+impl Lifecycle {
+    /// Returns `None` if `spawn_period` is 0, since fish must wait at least a day to spawn again.
+    fn new(spawn_period: usize, newborn_delay: usize) -> Option<Self> {
+        (spawn_period > 0).then_some(Self {
+            spawn_period,
+            newborn_delay,
+        })
+    }
+
+    fn num_timers(self) -> usize {
+        self.spawn_period + self.newborn_delay
+    }
+
+    /// The timer a fish resets to after spawning.
+    fn reset_timer(self) -> usize {
+        self.spawn_period - 1
+    }
+
+    /// The matrix that takes the number of fish at each timer value to the numbers a day later.
+    fn transition<T: Clone + Zero + One + CheckedAdd + CheckedMul>(self) -> Matrix<T> {
+        let size = self.num_timers();
+        let mut matrix = Matrix::zero(size);
+
+        // Decrease timer for all fish.
+        for timer in 1..size {
+            matrix[(timer - 1, timer)] = T::one();
+        }
+
+        // Reset timer for spawning fish, and spawn new fish.
+        for timer in [self.reset_timer(), size - 1] {
+            let cell = &mut matrix[(timer, 0)];
+            *cell = cell.checked_add(&T::one()).unwrap();
         }
-        Self(arr)
+
+        matrix
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::new(7, 2).unwrap()
     }
+}
+
+/// The number of fish with each timer value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Fish {
+    lifecycle: Lifecycle,
+    counts: Vec<u64>,
+}
 
-    fn evolve(mut self, days: u64) -> Self {
-        // Step over days in 9-day cycles.
-        for _ in 0..(days / 9) {
-            self = self.step_9();
+impl Fish {
+    fn new(lifecycle: Lifecycle, timers: Vec<u64>) -> Result<Self, InvalidTimer> {
+        let num_timers = lifecycle.num_timers();
+        let mut counts = vec![0; num_timers];
+        for timer in timers {
+            let count = usize::try_from(timer)
+                .ok()
+                .and_then(|timer| counts.get_mut(timer))
+                .ok_or(InvalidTimer { timer, num_timers })?;
+            *count += 1;
         }
+        Ok(Self { lifecycle, counts })
+    }
+
+    /// The number of fish with each timer value after `days` days.
+    ///
+    /// This is worked out exactly, since when some timers have no fish, the matrix power can have
+    /// entries far bigger than any of the counts.
+    fn counts_after(&self, days: u64) -> Vec<BigUint> {
+        let counts = self
+            .counts
+            .iter()
+            .map(|c| BigUint::from(*c))
+            .collect::<Vec<_>>();
+        self.lifecycle
+            .transition::<BigUint>()
+            .checked_pow(days)
+            .and_then(|matrix| matrix.checked_apply(&counts))
+            .expect("arbitrary precision can't overflow")
+    }
 
-        // Single step the remaining days.
-        for _ in 0..(days % 9) {
-            self = self.step();
+    /// The number of fish after `days` days.
+    fn count_after(&self, days: u64) -> BigUint {
+        self.counts_after(days).into_iter().sum()
+    }
+
+    /// The population on each day in `days`, or the first day it overflows a `u64`.
+    fn trajectory(&self, days: RangeInclusive<u64>) -> Result<Vec<Sample>, Overflow> {
+        let transition = self.lifecycle.transition::<u64>();
+        let first = *days.start();
+        let mut counts = self
+            .counts_after(first)
+            .iter()
+            .map(BigUint::to_u64)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Overflow { day: first })?;
+        let mut samples = Vec::new();
+        for day in days {
+            if !samples.is_empty() {
+                counts = transition.checked_apply(&counts).ok_or(Overflow { day })?;
+            }
+            let total = counts
+                .iter()
+                .try_fold(0u64, |acc, c| acc.checked_add(*c))
+                .ok_or(Overflow { day })?;
+            samples.push(Sample {
+                day,
+                total,
+                counts: counts.clone(),
+            });
         }
+        Ok(samples)
+    }
+}
 
-        self
+impl Simulation for Fish {
+    fn step(&mut self) {
+        let spawning = self.counts[0];
+        self.counts.rotate_left(1);
+        self.counts[self.lifecycle.reset_timer()] += spawning;
     }
+}
 
-    fn step(self) -> Self {
-        let mut new = [0; 9];
+/// A fish whose timer is too high for the lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct InvalidTimer {
+    timer: u64,
+    num_timers: usize,
+}
 
-        // Decrease timer for all fish.
-        new[..8].clone_from_slice(&self.0[1..]);
+impl fmt::Display for InvalidTimer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a fish has timer {}, but timers only go up to {}",
+            self.timer,
+            self.num_timers - 1
+        )
+    }
+}
 
-        // Reset timer for spawning fish.
-        new[6] += self.0[0];
+impl Error for InvalidTimer {}
 
-        // Spawn new fish.
-        new[8] = self.0[0];
+/// The day on which the population stops fitting in a `u64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Overflow {
+    day: u64,
+}
 
-        Self(new)
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the population overflows a u64 on day {}", self.day)
     }
+}
+
+impl Error for Overflow {}
+
+/// The population on a single day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Sample {
+    day: u64,
+    total: u64,
+    /// The number of fish with each timer value.
+    counts: Vec<u64>,
+}
 
-    fn step_9(self) -> Self {
-        let Self(x) = self;
-        Self([
-            x[0] + x[2],
-            x[1] + x[3],
-            x[2] + x[4],
-            x[3] + x[5],
-            x[4] + x[6],
-            x[5] + x[7] + x[0],
-            x[6] + x[8] + x[1],
-            x[7] + x[0],
-            x[8] + x[1],
-        ])
+/// Writes one row per sample, with a column for the total and one for each timer value.
+fn write_csv<W: Write>(samples: &[Sample], mut out: W) -> io::Result<()> {
+    let num_timers = samples.first().map_or(0, |s| s.counts.len());
+    write!(out, "day,total")?;
+    for timer in 0..num_timers {
+        write!(out, ",timer_{}", timer)?;
     }
+    writeln!(out)?;
 
-    fn count(self) -> u64 {
-        self.0.iter().sum()
+    for sample in samples {
+        write!(out, "{},{}", sample.day, sample.total)?;
+        for count in &sample.counts {
+            write!(out, ",{}", count)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// A square matrix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Matrix<T> {
+    size: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone + Zero + One + CheckedAdd + CheckedMul> Matrix<T> {
+    fn zero(size: usize) -> Self {
+        Self {
+            size,
+            cells: vec![T::zero(); size * size],
+        }
+    }
+
+    fn identity(size: usize) -> Self {
+        let mut matrix = Self::zero(size);
+        for i in 0..size {
+            matrix[(i, i)] = T::one();
+        }
+        matrix
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        let mut product = Self::zero(self.size);
+        for row in 0..self.size {
+            for col in 0..self.size {
+                let mut cell = T::zero();
+                for i in 0..self.size {
+                    let term = self[(row, i)].checked_mul(&rhs[(i, col)])?;
+                    cell = cell.checked_add(&term)?;
+                }
+                product[(row, col)] = cell;
+            }
+        }
+        Some(product)
+    }
+
+    /// Raises the matrix to the power `exp` by repeated squaring.
+    fn checked_pow(&self, mut exp: u64) -> Option<Self> {
+        let mut result = Self::identity(self.size);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.checked_mul(&base)?;
+            }
+            exp >>= 1;
+            // Skip the last squaring, since it isn't used and could overflow.
+            if exp > 0 {
+                base = base.checked_mul(&base)?;
+            }
+        }
+        Some(result)
+    }
+
+    fn checked_apply(&self, vector: &[T]) -> Option<Vec<T>> {
+        (0..self.size)
+            .map(|row| {
+                vector
+                    .iter()
+                    .enumerate()
+                    .try_fold(T::zero(), |acc, (i, v)| {
+                        acc.checked_add(&self[(row, i)].checked_mul(v)?)
+                    })
+            })
+            .collect()
+    }
+}
+
+impl<T> std::ops::Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        &self.cells[row * self.size + col]
+    }
+}
+
+impl<T> std::ops::IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        &mut self.cells[row * self.size + col]
     }
 }
 
@@ -90,9 +364,149 @@ fn fish(input: &str) -> IResult<&str, Vec<u64>> {
 mod tests {
     use super::*;
 
+    fn count(fish: &Fish) -> u64 {
+        fish.counts.iter().sum()
+    }
+
+    fn example() -> Fish {
+        Fish::new(Lifecycle::default(), vec![3, 4, 3, 1, 2]).unwrap()
+    }
+
     #[test]
     fn step_works() {
-        let fish = Fish([1, 0, 0, 0, 1, 1, 2, 0, 2]);
-        assert_eq!(Fish([0, 0, 0, 1, 1, 2, 1, 2, 1]), fish.step());
+        let mut fish = Fish {
+            lifecycle: Lifecycle::default(),
+            counts: vec![1, 0, 0, 0, 1, 1, 2, 0, 2],
+        };
+        fish.step();
+        assert_eq!(vec![0, 0, 0, 1, 1, 2, 1, 2, 1], fish.counts);
+    }
+
+    #[test]
+    fn example_counts_are_correct() {
+        let fish = example();
+        assert_eq!(BigUint::from(26u8), fish.count_after(18));
+        assert_eq!(BigUint::from(5934u16), fish.count_after(80));
+        assert_eq!(BigUint::from(26984457539u64), fish.count_after(256));
+    }
+
+    #[test]
+    fn matrix_matches_simulation() {
+        let lifecycles = [
+            Lifecycle::default(),
+            Lifecycle::new(3, 0).unwrap(),
+            Lifecycle::new(5, 4).unwrap(),
+            Lifecycle::new(1, 2).unwrap(),
+        ];
+        for lifecycle in lifecycles {
+            let start = Fish::new(lifecycle, vec![0, 1, 1, 2]).unwrap();
+            let mut fish = start.clone();
+            for days in 0..60 {
+                let counts = fish
+                    .counts
+                    .iter()
+                    .map(|c| BigUint::from(*c))
+                    .collect::<Vec<_>>();
+                assert_eq!(counts, start.counts_after(days));
+                assert_eq!(BigUint::from(count(&fish)), start.count_after(days));
+                fish.step();
+            }
+        }
+    }
+
+    #[test]
+    fn spawn_period_must_be_positive() {
+        assert_eq!(None, Lifecycle::new(0, 2));
+        assert_eq!(Some(Lifecycle::default()), Lifecycle::new(7, 2));
+    }
+
+    #[test]
+    fn timers_must_fit_the_lifecycle() {
+        let err = Fish::new(Lifecycle::default(), vec![3, 9, 1]).unwrap_err();
+        assert_eq!(
+            InvalidTimer {
+                timer: 9,
+                num_timers: 9
+            },
+            err
+        );
+        assert_eq!(
+            "a fish has timer 9, but timers only go up to 8",
+            err.to_string()
+        );
+        assert!(Fish::new(Lifecycle::new(3, 0).unwrap(), vec![2]).is_ok());
+        assert!(Fish::new(Lifecycle::default(), vec![u64::MAX]).is_err());
+    }
+
+    #[test]
+    fn counts_stay_exact_past_a_u64() {
+        let fish = example();
+        let days = 1000;
+        assert!(fish.count_after(days) > BigUint::from(u64::MAX));
+
+        let mut expected = fish.count_after(days - 9);
+        expected += fish.count_after(days - 7);
+        assert_eq!(expected, fish.count_after(days));
+    }
+
+    #[test]
+    fn unused_timers_dont_overflow_early() {
+        // A fish at timer 0 would have more descendants than fit in a u64 by day 510, but this
+        // one doesn't.
+        let fish = Fish::new(Lifecycle::default(), vec![8]).unwrap();
+        let samples = fish.trajectory(510..=510).unwrap();
+        assert_eq!(13662238643236844107, samples[0].total);
+        assert_eq!(BigUint::from(samples[0].total), fish.count_after(510));
+    }
+
+    #[test]
+    fn trajectory_matches_simulation() {
+        let start = example();
+        let samples = start.trajectory(10..=40).unwrap();
+        assert_eq!(31, samples.len());
+
+        let mut fish = start.clone();
+        fish.run(10);
+        for sample in &samples {
+            assert_eq!(fish.counts, sample.counts);
+            assert_eq!(count(&fish), sample.total);
+            fish.step();
+        }
+        assert_eq!((10, 12), (samples[0].day, samples[0].total));
+        assert_eq!((18, 26), (samples[8].day, samples[8].total));
+    }
+
+    #[test]
+    fn trajectory_detects_overflow() {
+        let err = example().trajectory(0..=1000).unwrap_err();
+        let max = BigUint::from(u64::MAX);
+        assert!(example().count_after(err.day) > max);
+        assert!(example().count_after(err.day - 1) <= max);
+        assert_eq!(Err(Overflow { day: 900 }), example().trajectory(900..=1000));
+    }
+
+    #[test]
+    fn days_default_to_star_2() {
+        let args =
+            |args: &[&str]| days(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>());
+        assert_eq!(Ok(0..=256), args(&[]));
+        assert_eq!(Ok(10..=256), args(&["10"]));
+        assert_eq!(Ok(10..=20), args(&["10", "20"]));
+        assert_eq!(Err(DaysError::Invalid("ten".to_string())), args(&["ten"]));
+        let err = args(&["20", "10"]).unwrap_err();
+        assert_eq!("last day 10 is before first day 20", err.to_string());
+    }
+
+    #[test]
+    fn csv_has_a_column_per_timer() {
+        let samples = example().trajectory(0..=1).unwrap();
+        let mut csv = Vec::new();
+        write_csv(&samples, &mut csv).unwrap();
+        let expected = "\
+day,total,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8
+0,5,0,1,1,2,1,0,0,0,0
+1,5,1,1,2,1,0,0,0,0,0
+";
+        assert_eq!(expected, String::from_utf8(csv).unwrap());
     }
 }
@@ -1,69 +1,378 @@
+use super::utils::IntervalSet;
 use nom::IResult;
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Write},
+};
 
 pub fn star_1(data: String) {
-    let mut crabs = parse(&data);
-    crabs.sort_unstable();
-
-    // Given the fuel function f, we know that the midpoint of the list mid is a local minimum.
-    // When the position is moved 1 to the left, all points on the left decrease by 1, and all
-    // points on the right increase by one. Additionally, if there is a point at mid, it increases
-    // by one. If there are an equal number of points to the left and right, that means that either
-    // the point to the left has the same fuel value as the current point, or greater. The same is
-    // true when we move 1 to the right. Therefore, if we start at the midpoint, moving can only
-    // either keep the fuel value the same or make it go up.
-
-    // We also know that there can be no maxima of the function. Assume that a point x is a maximum.
-    // Then f(x) >= f(x - 1) and f(x) >= f(x + 1). We can substitute equations based on f(a) (where
-    // left is the function that returns the number of points to the left of a, right is the
-    // function of the points to the right, and center is 1 or 0 depending on if there is a point
-    // at x):
-    // f(x - 1) = f(x) - left(a) + center(a) + right(a)
-    // f(x + 1) = f(x) + left(a) + center(a) - right(a)
-    // This can be rewritten as the system of inequalities:
-    // -left(a) + center(a) + right(a) <= 0
-    // left(a) + center(a) - right(a) <= 0
-    // Rearranging these, we have:
-    // left(a) >= right(a) + center(a)
-    // left(a) <= right(a) - center(a)
-    // This is a contradiction (since left, right, and center can only return positive numbers);
-    // therefore, no maxima can exist.
-
-    // Without any maxima, there can only be one minimum, which makes the midpoint the global
-    // minimum.
-
-    let mut mid = crabs[crabs.len() / 2];
-
-    // It's potentially possible for there to be multiple valid smallest fuel amounts; if the
-    // middle two points are only 1 distance apart, they are both valid candidates. For simplicity,
-    // we choose the point 1 after the point before the middle if there is no true midpoint.
-    if crabs.len() % 2 != 0 {
-        mid += 1;
-    }
-
-    let fuel = fuel_sum(&crabs[..], mid, |x| x);
-    println!("{}", fuel);
+    let crabs = parse(&data);
+    let alignment = align(&crabs[..], linear);
+    println!("{}", alignment.fuel);
 }
 
 pub fn star_2(data: String) {
-    fn triangle(x: u64) -> u64 {
-        (x * (x + 1)) / 2
-    }
+    let crabs = parse(&data);
+    let alignment = align(&crabs[..], triangular);
+    println!("{}", alignment.fuel);
+}
 
+/// Prints where the crabs meet and how much fuel that takes as CSV, with options given after the
+/// input file:
+///
+/// - the cost model, as `linear`, `triangular` or `quadratic`. The default is `triangular`.
+/// - `groups=<count>` to let the crabs split into up to that many groups, each with their own
+///   meeting point.
+/// - `allow=<positions>` to only let the crabs meet at those positions, and `forbid=<positions>`
+///   to stop them meeting at those positions. Positions are separated by commas, and can be
+///   ranges like `10..20`, which include both ends.
+pub fn alignments(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = options(args)?;
     let crabs = parse(&data);
+    let fuel = |dist| options.cost.fuel(dist);
+    let alignments = match options.allowed() {
+        Some(allowed) => vec![align_within(&crabs, &allowed, fuel).ok_or(NoAllowedPositions)?],
+        None => align_groups(&crabs, options.groups, fuel).expect("there's at least one group"),
+    };
+    write_csv(&alignments, io::stdout().lock())?;
+    Ok(())
+}
+
+/// The options for [`alignments`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Options {
+    cost: CostModel,
+    /// The most groups the crabs can split into, which is at least 1.
+    groups: usize,
+    /// The positions the crabs can meet at, or `None` for anywhere.
+    allowed: Option<IntervalSet>,
+    /// The positions the crabs can't meet at.
+    forbidden: IntervalSet,
+}
+
+impl Options {
+    /// The positions the crabs can meet at, or `None` if there are no restrictions.
+    fn allowed(&self) -> Option<IntervalSet> {
+        if self.allowed.is_none() && self.forbidden.is_empty() {
+            return None;
+        }
+        let allowed = self.allowed.clone();
+        Some(
+            allowed
+                .unwrap_or_else(|| IntervalSet::from(0..=i64::MAX))
+                .difference(&self.forbidden),
+        )
+    }
+}
+
+fn options(args: &[String]) -> Result<Options, OptionError> {
+    let mut options = Options {
+        cost: CostModel::Triangular,
+        groups: 1,
+        allowed: None,
+        forbidden: IntervalSet::new(),
+    };
+    for arg in args {
+        let invalid = || OptionError::Invalid(arg.clone());
+        if let Some(groups) = arg.strip_prefix("groups=") {
+            options.groups = groups.parse().ok().filter(|&g| g > 0).ok_or_else(invalid)?;
+        } else if let Some(positions) = arg.strip_prefix("allow=") {
+            let positions = self::positions(positions).ok_or_else(invalid)?;
+            let allowed = options.allowed.get_or_insert_with(IntervalSet::new);
+            *allowed = allowed.union(&positions);
+        } else if let Some(positions) = arg.strip_prefix("forbid=") {
+            let positions = self::positions(positions).ok_or_else(invalid)?;
+            options.forbidden = options.forbidden.union(&positions);
+        } else {
+            options.cost = arg.parse()?;
+        }
+    }
+    if options.groups > 1 && options.allowed().is_some() {
+        return Err(OptionError::Conflicting);
+    }
+    Ok(options)
+}
+
+/// Parses comma separated positions and ranges of positions, or returns `None` if any of them
+/// are invalid or negative.
+fn positions(list: &str) -> Option<IntervalSet> {
+    let position = |pos: &str| pos.parse::<i64>().ok().filter(|pos| *pos >= 0);
+    list.split(',')
+        .map(|range| match range.split_once("..") {
+            Some((start, end)) => Some(position(start)?..=position(end)?),
+            None => position(range).map(|pos| pos..=pos),
+        })
+        .collect()
+}
+
+/// None of the positions the crabs can meet at are allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NoAllowedPositions;
+
+impl fmt::Display for NoAllowedPositions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the crabs aren't allowed to meet anywhere")
+    }
+}
+
+impl Error for NoAllowedPositions {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OptionError {
+    Invalid(String),
+    /// Groups and allowed positions were both given.
+    Conflicting,
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(arg) => write!(
+                f,
+                "`{}` isn't an option, expected `linear`, `triangular`, `quadratic`, \
+                 `groups=<count>`, `allow=<positions>` or `forbid=<positions>`",
+                arg
+            ),
+            Self::Conflicting => write!(f, "crabs can't split into groups at allowed positions"),
+        }
+    }
+}
+
+impl Error for OptionError {}
 
-    // The best position is the one that reduces the distance to all points as much as possible.
-    // I do not have a proof of this yet.
-    let mean = crabs.iter().sum::<u64>() / crabs.len() as u64;
+/// How much fuel a crab uses to move a distance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum CostModel {
+    Linear,
+    Triangular,
+    Quadratic,
+}
+
+impl CostModel {
+    fn fuel(self, dist: u64) -> u64 {
+        match self {
+            Self::Linear => linear(dist),
+            Self::Triangular => triangular(dist),
+            Self::Quadratic => quadratic(dist),
+        }
+    }
+}
 
-    // I don't know why we can't just round mean here, but it doesn't work, so we have to check
-    // both floor and ceiling.
-    let left = fuel_sum(&crabs[..], mean, triangle);
-    let right = fuel_sum(&crabs[..], mean + 1, triangle);
-    let fuel = left.min(right);
-    println!("{}", fuel);
+impl std::str::FromStr for CostModel {
+    type Err = OptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "triangular" => Ok(Self::Triangular),
+            "quadratic" => Ok(Self::Quadratic),
+            _ => Err(OptionError::Invalid(s.to_string())),
+        }
+    }
+}
+
+/// Each step costs one unit of fuel.
+fn linear(dist: u64) -> u64 {
+    dist
+}
+
+/// Each step costs one more unit of fuel than the last.
+fn triangular(dist: u64) -> u64 {
+    (dist * (dist + 1)) / 2
+}
+
+/// Fuel grows with the square of the distance.
+fn quadratic(dist: u64) -> u64 {
+    dist * dist
+}
+
+/// A crab whose fuel use is multiplied by `weight`.
+///
+/// Crabs are written as their position, optionally followed by `*` and their weight, which
+/// defaults to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Crab {
+    position: u64,
+    weight: u64,
+}
+
+impl Crab {
+    fn new(position: u64) -> Self {
+        Self {
+            position,
+            weight: 1,
+        }
+    }
+}
+
+/// Where the crabs meet, and how much fuel it takes to get there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Alignment {
+    position: u64,
+    fuel: u64,
+}
+
+/// Finds the position that takes the least total fuel to reach, preferring the leftmost one if
+/// there's a tie.
+///
+/// `dist_to_fuel` must be convex and nondecreasing. Then each crab's fuel is a convex function of
+/// the position, and so is their sum, which means the fuel only ever decreases and then increases
+/// as the position moves right. The minimum is the first position where moving right doesn't save
+/// any fuel, which we can binary search for.
+fn align<F>(crabs: &[Crab], dist_to_fuel: F) -> Alignment
+where
+    F: Fn(u64) -> u64,
+{
+    let fuel = |pos| fuel_sum(crabs, pos, &dist_to_fuel);
+
+    // The minimum can't be outside the crabs, since moving towards them saves fuel for all of them.
+    let mut low = crabs.iter().map(|c| c.position).min().unwrap_or(0);
+    let mut high = crabs.iter().map(|c| c.position).max().unwrap_or(0);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if fuel(mid) <= fuel(mid + 1) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+
+    Alignment {
+        position: low,
+        fuel: fuel(low),
+    }
 }
 
-fn fuel_sum<F>(crabs: &[u64], pos: u64, dist_to_fuel: F) -> u64
+/// Like [`align`], but the crabs may only meet at one of the `allowed` positions. Negative
+/// positions are ignored.
+///
+/// Returns `None` if no positions are allowed.
+fn align_within<F>(crabs: &[Crab], allowed: &IntervalSet, dist_to_fuel: F) -> Option<Alignment>
+where
+    F: Fn(u64) -> u64,
+{
+    let allowed = allowed.intersection(&IntervalSet::from(0..=i64::MAX));
+
+    // The fuel only increases moving away from the best position, so the only candidates are the
+    // closest allowed positions on either side of it.
+    let best = i64::try_from(align(crabs, &dist_to_fuel).position).unwrap_or(i64::MAX);
+    let below = allowed
+        .iter()
+        .take_while(|range| *range.start() <= best)
+        .last()
+        .map(|range| (*range.end()).min(best));
+    let above = allowed
+        .iter()
+        .find(|range| *range.end() >= best)
+        .map(|range| (*range.start()).max(best));
+
+    [below, above]
+        .into_iter()
+        .flatten()
+        .map(|position| {
+            let position = position as u64;
+            Alignment {
+                position,
+                fuel: fuel_sum(crabs, position, &dist_to_fuel),
+            }
+        })
+        .min_by_key(|alignment| (alignment.fuel, alignment.position))
+}
+
+/// Splits the crabs into at most `groups` groups that each meet at their own position, using as
+/// little total fuel as possible.
+///
+/// The alignments are returned from left to right, or `None` if there are crabs but no groups.
+///
+/// With a convex cost, the crabs in each group in an optimal split are next to each other when
+/// sorted by position, so the split is found with dynamic programming over the sorted crabs:
+/// `best[g][j]` is the least fuel for the first `j` crabs to meet in `g` groups.
+fn align_groups<F>(crabs: &[Crab], groups: usize, dist_to_fuel: F) -> Option<Vec<Alignment>>
+where
+    F: Fn(u64) -> u64,
+{
+    let mut crabs = crabs.to_vec();
+    crabs.sort_unstable();
+    let n = crabs.len();
+    if n == 0 {
+        return Some(Vec::new());
+    }
+    if groups == 0 {
+        return None;
+    }
+    // Splitting a group never takes more fuel, so there's no point having more groups than crabs.
+    let groups = groups.min(n);
+    let segments = segment_alignments(&crabs, &dist_to_fuel);
+
+    let mut best = vec![vec![None; n + 1]; groups + 1];
+    let mut split = vec![vec![0; n + 1]; groups + 1];
+    best[0][0] = Some(0);
+    for g in 1..=groups {
+        for end in g..=n {
+            for start in (g - 1)..end {
+                let fuel = match best[g - 1][start] {
+                    Some(fuel) => fuel + segments[start][end - start - 1].fuel,
+                    None => continue,
+                };
+                if best[g][end].is_none_or(|b| fuel < b) {
+                    best[g][end] = Some(fuel);
+                    split[g][end] = start;
+                }
+            }
+        }
+    }
+
+    let mut alignments = Vec::with_capacity(groups);
+    let mut end = n;
+    for g in (1..=groups).rev() {
+        let start = split[g][end];
+        alignments.push(segments[start][end - start - 1]);
+        end = start;
+    }
+    alignments.reverse();
+    Some(alignments)
+}
+
+/// The best alignment of every run of consecutive crabs, where `segments[start][len - 1]` is for
+/// the `len` crabs from `start`. The crabs must be sorted by position.
+fn segment_alignments<F>(crabs: &[Crab], dist_to_fuel: F) -> Vec<Vec<Alignment>>
+where
+    F: Fn(u64) -> u64,
+{
+    (0..crabs.len())
+        .map(|start| {
+            // Adding a crab further right can only move the best position right, so it never has
+            // to be searched for from scratch.
+            let mut position = crabs[start].position;
+            (start..crabs.len())
+                .map(|end| {
+                    let segment = &crabs[start..=end];
+                    let mut fuel = fuel_sum(segment, position, &dist_to_fuel);
+                    while position < crabs[end].position {
+                        let next = fuel_sum(segment, position + 1, &dist_to_fuel);
+                        if next >= fuel {
+                            break;
+                        }
+                        position += 1;
+                        fuel = next;
+                    }
+                    Alignment { position, fuel }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn write_csv<W: Write>(alignments: &[Alignment], mut out: W) -> io::Result<()> {
+    writeln!(out, "position,fuel")?;
+    for alignment in alignments {
+        writeln!(out, "{},{}", alignment.position, alignment.fuel)?;
+    }
+    Ok(())
+}
+
+fn fuel_sum<F>(crabs: &[Crab], pos: u64, dist_to_fuel: F) -> u64
 where
     F: Fn(u64) -> u64,
 {
@@ -77,17 +386,266 @@ where
 
     crabs
         .iter()
-        .map(|&crab| abs_min(crab, pos))
-        .map(dist_to_fuel)
+        .map(|crab| crab.weight * dist_to_fuel(abs_min(crab.position, pos)))
         .sum()
 }
 
-fn parse(input: &str) -> Vec<u64> {
+fn parse(input: &str) -> Vec<Crab> {
     super::utils::parse(crabs, input)
 }
 
-fn crabs(input: &str) -> IResult<&str, Vec<u64>> {
-    use nom::{bytes::complete::tag, character::complete::u64 as u64_, multi::separated_list0};
+fn crabs(input: &str) -> IResult<&str, Vec<Crab>> {
+    use nom::{bytes::complete::tag, multi::separated_list0};
+
+    separated_list0(tag(","), crab)(input)
+}
+
+fn crab(input: &str) -> IResult<&str, Crab> {
+    use nom::{
+        character::complete::{char as char_, u64 as u64_},
+        combinator::opt,
+        sequence::preceded,
+    };
 
-    separated_list0(tag(","), u64_)(input)
+    let (input, position) = u64_(input)?;
+    let (input, weight) = opt(preceded(char_('*'), u64_))(input)?;
+    let crab = match weight {
+        Some(weight) => Crab { position, weight },
+        None => Crab::new(position),
+    };
+    Ok((input, crab))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    const EXAMPLE: &str = "16,1,2,0,4,2,7,1,2,14";
+
+    fn brute_force<F: Fn(u64) -> u64>(crabs: &[Crab], dist_to_fuel: F) -> Alignment {
+        let max = crabs.iter().map(|c| c.position).max().unwrap();
+        (0..=max)
+            .map(|position| Alignment {
+                position,
+                fuel: fuel_sum(crabs, position, &dist_to_fuel),
+            })
+            .min_by_key(|a| (a.fuel, a.position))
+            .unwrap()
+    }
+
+    #[test]
+    fn example_alignments_are_correct() {
+        let crabs = parse(EXAMPLE);
+        let expected = Alignment {
+            position: 2,
+            fuel: 37,
+        };
+        assert_eq!(expected, align(&crabs[..], linear));
+        let expected = Alignment {
+            position: 5,
+            fuel: 168,
+        };
+        assert_eq!(expected, align(&crabs[..], triangular));
+    }
+
+    #[test]
+    fn options_are_parsed() {
+        let args =
+            |args: &[&str]| options(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>());
+        let default = Options {
+            cost: CostModel::Triangular,
+            groups: 1,
+            allowed: None,
+            forbidden: IntervalSet::new(),
+        };
+        assert_eq!(Ok(default.clone()), args(&[]));
+        let expected = Options {
+            cost: CostModel::Quadratic,
+            groups: 3,
+            ..default.clone()
+        };
+        assert_eq!(Ok(expected), args(&["groups=3", "quadratic"]));
+        let expected = Options {
+            allowed: Some([0..=0, 4..=7, 10..=20].into_iter().collect()),
+            forbidden: IntervalSet::from(15..=15),
+            ..default
+        };
+        let parsed = args(&["allow=4,10..20", "forbid=15", "allow=0,5..7"]);
+        assert_eq!(Ok(expected), parsed);
+        let allowed = [0..=0, 4..=7, 10..=14, 16..=20].into_iter().collect();
+        assert_eq!(Some(allowed), parsed.unwrap().allowed());
+
+        for arg in [
+            "cubic",
+            "groups=0",
+            "groups=",
+            "allow=",
+            "allow=1,,2",
+            "allow=-1",
+            "forbid=1..",
+        ] {
+            let err = args(&[arg]).unwrap_err();
+            assert_eq!(OptionError::Invalid(arg.to_string()), err);
+        }
+        assert_eq!(
+            Err(OptionError::Conflicting),
+            args(&["groups=2", "forbid=1"])
+        );
+        assert_eq!(
+            "`cubic` isn't an option, expected `linear`, `triangular`, `quadratic`, \
+             `groups=<count>`, `allow=<positions>` or `forbid=<positions>`",
+            args(&["cubic"]).unwrap_err().to_string()
+        );
+        assert_eq!(9, CostModel::Quadratic.fuel(3));
+    }
+
+    #[test]
+    fn alignments_match_brute_force() {
+        let crabs = parse(EXAMPLE);
+        let cube = |dist: u64| dist * dist * dist;
+        let capped = |dist: u64| dist.saturating_sub(3);
+        assert_eq!(brute_force(&crabs, linear), align(&crabs, linear));
+        assert_eq!(brute_force(&crabs, triangular), align(&crabs, triangular));
+        assert_eq!(brute_force(&crabs, quadratic), align(&crabs, quadratic));
+        assert_eq!(brute_force(&crabs, cube), align(&crabs, cube));
+        assert_eq!(brute_force(&crabs, capped), align(&crabs, capped));
+    }
+
+    #[test]
+    fn ties_prefer_the_leftmost_position() {
+        let alignment = align(&[Crab::new(3), Crab::new(7)], linear);
+        assert_eq!(
+            Alignment {
+                position: 3,
+                fuel: 4
+            },
+            alignment
+        );
+    }
+
+    #[test]
+    fn weights_are_parsed_and_applied() {
+        let crabs = parse("1*3,10,4*2");
+        let expected = vec![
+            Crab {
+                position: 1,
+                weight: 3,
+            },
+            Crab::new(10),
+            Crab {
+                position: 4,
+                weight: 2,
+            },
+        ];
+        assert_eq!(expected, crabs);
+        assert_eq!(3 * 3 + 6, fuel_sum(&crabs, 4, linear));
+        assert_eq!(brute_force(&crabs, triangular), align(&crabs, triangular));
+        assert_eq!(
+            Alignment {
+                position: 1,
+                fuel: 15
+            },
+            align(&crabs, linear)
+        );
+    }
+
+    #[test]
+    fn alignment_can_be_restricted() {
+        let crabs = parse(EXAMPLE);
+        let allowed = [10..=10, 0..=0, 7..=7, 4..=4].into_iter().collect();
+        let alignment = align_within(&crabs, &allowed, linear);
+        assert_eq!(
+            Some(Alignment {
+                position: 4,
+                fuel: fuel_sum(&crabs, 4, linear)
+            }),
+            alignment
+        );
+        assert_eq!(
+            Some(brute_force(&crabs, triangular)),
+            align_within(&crabs, &IntervalSet::from(-5..=16), triangular)
+        );
+        assert_eq!(None, align_within(&crabs, &IntervalSet::new(), linear));
+        assert_eq!(
+            None,
+            align_within(&crabs, &IntervalSet::from(-5..=-1), linear)
+        );
+    }
+
+    #[test]
+    fn restricted_alignments_match_brute_force() {
+        let crabs = parse("16*2,1,2,0*3,4,2,7,1,2,14*4,9,30");
+        let sets = [[0..=1, 9..=12], [3..=4, 6..=6], [20..=25, 40..=50]];
+        for ranges in sets {
+            let allowed = ranges.clone().into_iter().collect::<IntervalSet>();
+            for dist_to_fuel in [linear, triangular, quadratic] {
+                let expected = ranges
+                    .clone()
+                    .into_iter()
+                    .flatten()
+                    .map(|position| {
+                        let position = position as u64;
+                        Alignment {
+                            position,
+                            fuel: fuel_sum(&crabs, position, dist_to_fuel),
+                        }
+                    })
+                    .min_by_key(|a| (a.fuel, a.position));
+                assert_eq!(expected, align_within(&crabs, &allowed, dist_to_fuel));
+            }
+        }
+    }
+
+    #[test]
+    fn groups_split_at_gaps() {
+        let crabs = parse("1,2,3,20,21,22*5,50");
+        let groups = align_groups(&crabs, 2, linear).unwrap();
+        let positions = groups.iter().map(|a| a.position).collect::<Vec<_>>();
+        assert_eq!(vec![2, 22], positions);
+
+        let groups = align_groups(&crabs, 3, linear).unwrap();
+        let expected =
+            [(2, 2), (22, 3), (50, 0)].map(|(position, fuel)| Alignment { position, fuel });
+        assert_eq!(&expected[..], &groups[..]);
+
+        let all = align_groups(&crabs, 100, triangular).unwrap();
+        assert_eq!(7, all.len());
+        assert!(all.iter().all(|a| a.fuel == 0));
+        assert_eq!(None, align_groups(&crabs, 0, linear));
+    }
+
+    #[test]
+    fn one_group_matches_align() {
+        let crabs = parse("16*2,1,2,0*3,4,2,7,1,2,14*4");
+        for dist_to_fuel in [linear, triangular, quadratic] {
+            let groups = align_groups(&crabs, 1, dist_to_fuel).unwrap();
+            assert_eq!(vec![align(&crabs, dist_to_fuel)], groups);
+        }
+    }
+
+    #[test]
+    fn groups_match_brute_force() {
+        let crabs = parse("16*2,1,2,0*3,4,2,7,1,2,14*4,9,30");
+        let mut sorted = crabs.clone();
+        sorted.sort_unstable();
+        for groups in 1..=4 {
+            let best = (1..sorted.len())
+                .combinations(groups - 1)
+                .map(|cuts| {
+                    let mut bounds = vec![0];
+                    bounds.extend(cuts);
+                    bounds.push(sorted.len());
+                    bounds
+                        .windows(2)
+                        .map(|w| brute_force(&sorted[w[0]..w[1]], triangular).fuel)
+                        .sum::<u64>()
+                })
+                .min()
+                .unwrap();
+            let found = align_groups(&crabs, groups, triangular).unwrap();
+            assert_eq!(groups, found.len());
+            assert_eq!(best, found.iter().map(|a| a.fuel).sum::<u64>());
+        }
+    }
 }
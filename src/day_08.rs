@@ -1,5 +1,5 @@
 use nom::IResult;
-use std::ops;
+use std::{fmt, ops};
 
 pub fn star_1(data: String) {
     let entries = parse(&data);
@@ -12,10 +12,43 @@ pub fn star_1(data: String) {
 
 pub fn star_2(data: String) {
     let entries = parse(&data);
-    let sum = entries.iter().map(|e| e.get_output().unwrap()).sum::<u64>();
+    let mut sum = 0;
+    for output in decode_all(&entries) {
+        match output {
+            Ok(output) => sum += output,
+            Err(err) => eprintln!("{}", err),
+        }
+    }
     println!("{}", sum);
 }
 
+/// Draws the output of each entry as it would look on a display that was wired up correctly.
+pub fn display(data: String) {
+    let entries = parse(&data);
+    for (index, entry) in entries.into_iter().enumerate() {
+        let wiring = entry
+            .check_signals(&Digit::DECIMAL)
+            .and_then(|()| entry.wiring(&Digit::DECIMAL));
+        match wiring {
+            Ok(wiring) => println!("{}\n", render(&entry.output, Some(wiring))),
+            Err(error) => eprintln!("{}", EntryError { index, error }),
+        }
+    }
+}
+
+/// Decodes the output of every entry, carrying on past the ones that can't be decoded.
+fn decode_all(entries: &[Entry]) -> Vec<Result<u64, EntryError>> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            entry
+                .get_output()
+                .map_err(|error| EntryError { index, error })
+        })
+        .collect()
+}
+
 fn parse(input: &str) -> Vec<Entry> {
     super::utils::parse(entries, input)
 }
@@ -31,114 +64,373 @@ struct Entry {
 }
 
 impl Entry {
-    pub fn get_output(self) -> Result<u64, &'static str> {
-        let mut one = None;
-        let mut four = None;
-        let mut five_seg_a = None;
-        let mut five_seg_b = None;
-        let mut five_seg_c = None;
-        let mut six_seg_a = None;
-        let mut six_seg_b = None;
-        let mut six_seg_c = None;
-        let mut seven = None;
-        let mut eight = None;
-
-        macro_rules! set_or_err {
-            ($a:ident, $v:expr, $err:expr) => {
-                if $a == None {
-                    $a = Some($v);
-                } else {
-                    return Err($err);
-                }
-            };
+    /// An entry showing the `output` digits on a display wired up by `wiring`, with the signals in
+    /// an order picked by `rng`, or `None` if an output isn't a decimal digit.
+    #[cfg(test)]
+    pub fn generate(wiring: Wiring, output: [u8; 4], rng: &mut crate::utils::Rng) -> Option<Self> {
+        let inverse = wiring.inverse();
+        let mut signals = Digit::DECIMAL.map(|d| inverse.apply(d));
+        rng.shuffle(&mut signals);
+        let mut digits = [Digit::EMPTY; 4];
+        for (digit, value) in digits.iter_mut().zip(output) {
+            *digit = inverse.apply(*Digit::DECIMAL.get(usize::from(value))?);
         }
+        Some(Self {
+            signals,
+            output: digits,
+        })
+    }
 
-        macro_rules! set_cascade_or_err {
-            ($a:ident, $b:ident, $c:ident, $v:expr, $err:expr) => {
-                if $a == None {
-                    $a = Some($v);
-                } else if $b == None {
-                    $b = Some($v);
-                } else if $c == None {
-                    $c = Some($v);
-                } else {
-                    return Err($err);
-                }
-            };
+    pub fn get_output(self) -> Result<u64, DecodeError> {
+        self.check_signals(&Digit::DECIMAL)?;
+        self.decode(&Digit::DECIMAL)
+    }
+
+    /// Checks that the signals could show each of the `glyphs` once, going by how many segments
+    /// they light.
+    fn check_signals(&self, glyphs: &[Digit]) -> Result<(), DecodeError> {
+        if let Some(signal) = self
+            .signals
+            .iter()
+            .find(|d| glyphs.iter().all(|g| g.num_segments() != d.num_segments()))
+        {
+            return Err(DecodeError::UnexpectedSignal(*signal));
         }
 
-        for digit in self.signals {
-            match digit.num_segments() {
-                2 => set_or_err!(one, digit, "more than one 1"),
-                3 => set_or_err!(seven, digit, "more than one 7"),
-                4 => set_or_err!(four, digit, "more than one 4"),
-                5 => set_cascade_or_err!(
-                    five_seg_a,
-                    five_seg_b,
-                    five_seg_c,
-                    digit,
-                    "more than 3 5-segments"
-                ),
-                6 => set_cascade_or_err!(
-                    six_seg_a,
-                    six_seg_b,
-                    six_seg_c,
-                    digit,
-                    "more than 3 6-segments"
-                ),
-                7 => set_or_err!(eight, digit, "more than one 8"),
-                _ => return Err("invalid digit"),
+        for num_segments in 0..=Segment::ALL.len() as u8 {
+            let expected = glyphs
+                .iter()
+                .filter(|g| g.num_segments() == num_segments)
+                .count();
+            let signals = self
+                .signals
+                .iter()
+                .copied()
+                .filter(|d| d.num_segments() == num_segments)
+                .collect::<Vec<_>>();
+            if signals.len() != expected {
+                return Err(DecodeError::WrongSignalCount {
+                    num_segments,
+                    expected,
+                    signals,
+                });
             }
         }
+        Ok(())
+    }
+
+    /// Works out the wiring from every pattern in the entry, and reads the output as a number in
+    /// base `glyphs.len()`, where each glyph stands for its index.
+    pub fn decode(self, glyphs: &[Digit]) -> Result<u64, DecodeError> {
+        let wiring = self.wiring(glyphs)?;
+        Ok(self.output.iter().fold(0, |acc, wires| {
+            let segments = wiring.apply(*wires);
+            let value = glyphs.iter().position(|g| *g == segments).unwrap();
+            acc * glyphs.len() as u64 + value as u64
+        }))
+    }
 
-        let one = one.ok_or("no 1 given")?;
-        let four = four.ok_or("no 4 given")?;
-        let five_seg_a = five_seg_a.ok_or("no 5-segment given")?;
-        let five_seg_b = five_seg_b.ok_or("only one 5-segment given")?;
-        let five_seg_c = five_seg_c.ok_or("only two 5-segments given")?;
-        let six_seg_a = six_seg_a.ok_or("no 6-segment given")?;
-        let six_seg_b = six_seg_b.ok_or("only one 6-segment given")?;
-        let six_seg_c = six_seg_c.ok_or("only two 6-segments given")?;
-        let seven = seven.ok_or("no 7 given")?;
-        let eight = eight.ok_or("no 8 given")?;
-
-        let fives = five_seg_a & five_seg_b & five_seg_c;
-        let sixes = six_seg_a & six_seg_b & six_seg_c;
-
-        let a = !one & seven;
-        let c = one & !sixes;
-        let d = four & fives;
-        let e = !four & !sixes;
-        let f = one & sixes;
-
-        let g = !a & fives & sixes;
-        let b = four & !one & !d;
-
-        macro_rules! seg {
-            ($x:ident) => {
-                $x.try_to_segment().ok_or_else(|| "Digit not a segment")?
-            };
+    /// A wiring that turns every pattern in the entry into one of the `glyphs`.
+    ///
+    /// The wiring doesn't have to be unique, as long as every wiring that fits shows the same
+    /// output.
+    pub fn wiring(self, glyphs: &[Digit]) -> Result<Wiring, DecodeError> {
+        let patterns = self.signals.iter().chain(&self.output).copied();
+        if let Some(signal) = patterns
+            .clone()
+            .find(|d| glyphs.iter().all(|g| g.num_segments() != d.num_segments()))
+        {
+            return Err(DecodeError::UnexpectedSignal(signal));
         }
 
-        let seg_map = [
-            (seg!(a), Segment::A),
-            (seg!(b), Segment::B),
-            (seg!(c), Segment::C),
-            (seg!(d), Segment::D),
-            (seg!(e), Segment::E),
-            (seg!(f), Segment::F),
-            (seg!(g), Segment::G),
-        ];
+        let patterns = patterns.collect::<Vec<_>>();
+        let wirings = match solve_wiring(&patterns, glyphs) {
+            Solution::Unique(wiring) => return Ok(wiring),
+            Solution::Ambiguous(wirings) => wirings,
+            Solution::Contradictory => return Err(self.diagnose(&patterns, glyphs)),
+        };
+
+        let shown = |wiring: &Wiring| self.output.map(|wires| wiring.apply(wires));
+        let output = shown(&wirings[0]);
+        if wirings.iter().all(|w| shown(w) == output) {
+            return Ok(wirings[0]);
+        }
 
-        let mut sum: u64 = 0;
+        // Some segment must be driven by different wires in different wirings.
+        let (segment, wires) = Segment::ALL
+            .into_iter()
+            .map(|segment| {
+                let wires = wirings.iter().fold(Digit::EMPTY, |wires, w| {
+                    wires.with_set(w.inverse().0[segment as usize])
+                });
+                (segment, wires)
+            })
+            .find(|(_, wires)| wires.num_segments() > 1)
+            .unwrap();
+        Err(DecodeError::UnresolvedSegment { segment, wires })
+    }
+
+    /// Why no wiring fits `patterns`. If the signals pin down the wiring on their own, it's the
+    /// first output that doesn't read as a glyph. Otherwise it's a set of patterns that no wiring
+    /// fits, where every wiring fits the rest once any one of them is dropped.
+    fn diagnose(&self, patterns: &[Digit], glyphs: &[Digit]) -> DecodeError {
+        if let Solution::Unique(wiring) = solve_wiring(&self.signals, glyphs) {
+            let unknown = self
+                .output
+                .iter()
+                .enumerate()
+                .find_map(|(position, wires)| {
+                    let segments = wiring.apply(*wires);
+                    (!glyphs.contains(&segments)).then_some(DecodeError::UnknownOutput {
+                        position,
+                        wires: *wires,
+                        segments,
+                    })
+                });
+            if let Some(unknown) = unknown {
+                return unknown;
+            }
+        }
+
+        let mut conflicting = patterns.to_vec();
+        conflicting.sort_unstable_by_key(|d| d.0);
+        conflicting.dedup();
+        let mut idx = 0;
+        while idx < conflicting.len() {
+            let pattern = conflicting.remove(idx);
+            if solve_wiring(&conflicting, glyphs) != Solution::Contradictory {
+                conflicting.insert(idx, pattern);
+                idx += 1;
+            }
+        }
+        DecodeError::Contradictory {
+            patterns: conflicting,
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for signal in self.signals {
+            write!(f, "{} ", signal)?;
+        }
+        write!(f, "|")?;
         for digit in self.output {
-            sum *= 10;
-            sum += digit
-                .transform(&seg_map)
-                .try_to_u8()
-                .ok_or("Digit not a real digit")? as u64;
+            write!(f, " {}", digit)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which segment each wire drives, indexed by wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Wiring([Segment; 7]);
+
+impl Wiring {
+    pub const IDENTITY: Self = Self(Segment::ALL);
+
+    /// A wiring picked by `rng`.
+    #[cfg(test)]
+    pub fn random(rng: &mut crate::utils::Rng) -> Self {
+        let mut wiring = Self::IDENTITY;
+        rng.shuffle(&mut wiring.0);
+        wiring
+    }
+
+    /// The wire to segment pairs, in the form [`Digit::transform`] takes.
+    pub fn seg_map(self) -> [(Segment, Segment); 7] {
+        Segment::ALL.map(|wire| (wire, self.0[wire as usize]))
+    }
+
+    /// The segments lit by the given wires.
+    pub fn apply(self, wires: Digit) -> Digit {
+        wires.transform(&self.seg_map())
+    }
+
+    /// The wiring that takes segments back to the wires that drive them.
+    pub fn inverse(self) -> Self {
+        let mut inverse = Self::IDENTITY;
+        for wire in Segment::ALL {
+            inverse.0[self.0[wire as usize] as usize] = wire;
+        }
+        inverse
+    }
+}
+
+/// The wirings that fit some observed patterns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solution {
+    Unique(Wiring),
+    Ambiguous(Vec<Wiring>),
+    /// No wiring turns every pattern into a glyph.
+    Contradictory,
+}
+
+/// Finds every wiring that turns each of the `patterns` into one of the `glyphs`.
+///
+/// Wires are assigned segments one at a time, and a partial wiring is abandoned as soon as some
+/// pattern can't become any glyph with the right number of segments: the wires assigned so far
+/// that are in the pattern must light segments of the glyph, and the ones that aren't mustn't.
+pub fn solve_wiring(patterns: &[Digit], glyphs: &[Digit]) -> Solution {
+    let mut patterns = patterns.to_vec();
+    patterns.sort_unstable_by_key(|d| d.0);
+    patterns.dedup();
+
+    let mut search = WiringSearch {
+        patterns,
+        glyphs,
+        wiring: Wiring::IDENTITY,
+        used: Digit::EMPTY,
+        solutions: Vec::new(),
+    };
+    search.assign(0);
+
+    let mut solutions = search.solutions;
+    match solutions.len() {
+        0 => Solution::Contradictory,
+        1 => Solution::Unique(solutions.pop().unwrap()),
+        _ => Solution::Ambiguous(solutions),
+    }
+}
+
+struct WiringSearch<'a> {
+    patterns: Vec<Digit>,
+    glyphs: &'a [Digit],
+    /// Only the first few wires have been assigned at any point in the search.
+    wiring: Wiring,
+    /// The segments that already have a wire.
+    used: Digit,
+    solutions: Vec<Wiring>,
+}
+
+impl<'a> WiringSearch<'a> {
+    fn assign(&mut self, num_assigned: usize) {
+        if num_assigned == Segment::ALL.len() {
+            self.solutions.push(self.wiring);
+            return;
         }
-        Ok(sum)
+
+        for segment in Segment::ALL {
+            if self.used.is_set(segment) {
+                continue;
+            }
+            self.wiring.0[num_assigned] = segment;
+            self.used = self.used.with_set(segment);
+            if self.is_consistent(num_assigned + 1) {
+                self.assign(num_assigned + 1);
+            }
+            self.used = Digit(self.used.0 & !segment.mask());
+        }
+    }
+
+    fn is_consistent(&self, num_assigned: usize) -> bool {
+        let assigned = Digit((1 << num_assigned) - 1);
+        self.patterns.iter().all(|pattern| {
+            let lit = self.wiring.apply(*pattern & assigned);
+            let unlit = self.wiring.apply(!*pattern & assigned);
+            self.glyphs.iter().any(|glyph| {
+                glyph.num_segments() == pattern.num_segments()
+                    && (lit & !*glyph) == Digit::EMPTY
+                    && (unlit & *glyph) == Digit::EMPTY
+            })
+        })
+    }
+}
+
+/// Why an entry's output couldn't be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A pattern lights a number of segments that no glyph does.
+    UnexpectedSignal(Digit),
+    /// The signals don't include exactly one of each digit, so there are the wrong number of
+    /// signals that light `num_segments` segments.
+    WrongSignalCount {
+        num_segments: u8,
+        expected: usize,
+        signals: Vec<Digit>,
+    },
+    /// No wiring turns all of `patterns` into glyphs, though one does for any of them but one.
+    Contradictory { patterns: Vec<Digit> },
+    /// The signals don't pin down which wire drives `segment`; any of `wires` could, and they
+    /// read the output differently.
+    UnresolvedSegment { segment: Segment, wires: Digit },
+    /// The output digit at `position` lights `segments` once unscrambled, which isn't a digit.
+    UnknownOutput {
+        position: usize,
+        wires: Digit,
+        segments: Digit,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedSignal(signal) => write!(
+                f,
+                "signal {} has {} segments, which isn't a digit",
+                signal,
+                signal.num_segments()
+            ),
+            Self::WrongSignalCount {
+                num_segments,
+                expected,
+                signals,
+            } => {
+                write!(
+                    f,
+                    "expected {} signal(s) with {} segments, found {}",
+                    expected,
+                    num_segments,
+                    signals.len()
+                )?;
+                for (idx, signal) in signals.iter().enumerate() {
+                    write!(f, "{}{}", if idx == 0 { ": " } else { ", " }, signal)?;
+                }
+                Ok(())
+            }
+            Self::Contradictory { patterns } => {
+                write!(f, "no wiring fits all of")?;
+                for (idx, pattern) in patterns.iter().enumerate() {
+                    write!(f, "{}{}", if idx == 0 { " " } else { ", " }, pattern)?;
+                }
+                Ok(())
+            }
+            Self::UnresolvedSegment { segment, wires } => write!(
+                f,
+                "couldn't tell which wire drives segment {}, candidates are \"{}\"",
+                segment, wires
+            ),
+            Self::UnknownOutput {
+                position,
+                wires,
+                segments,
+            } => write!(
+                f,
+                "output digit {} ({}) decodes to {}, which isn't a digit",
+                position, wires, segments
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A [`DecodeError`] for the entry at `index`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryError {
+    index: usize,
+    error: DecodeError,
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "entry {}: {}", self.index + 1, self.error)
+    }
+}
+
+impl std::error::Error for EntryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
     }
 }
 
@@ -164,7 +456,6 @@ pub struct Digit(u8);
 
 impl Digit {
     const EMPTY: Self = Self(0b00000000);
-    const FULL: Self = Self(0b01111111);
     const ZERO: Self = Self(0b01110111);
     const ONE: Self = Self(0b00100100);
     const TWO: Self = Self(0b01011101);
@@ -176,6 +467,19 @@ impl Digit {
     const EIGHT: Self = Self(0b01111111);
     const NINE: Self = Self(0b01101111);
 
+    pub const DECIMAL: [Self; 10] = [
+        Self::ZERO,
+        Self::ONE,
+        Self::TWO,
+        Self::THREE,
+        Self::FOUR,
+        Self::FIVE,
+        Self::SIX,
+        Self::SEVEN,
+        Self::EIGHT,
+        Self::NINE,
+    ];
+
     pub const fn num_segments(self) -> u8 {
         self.0.count_ones() as u8
     }
@@ -192,6 +496,7 @@ impl Digit {
         Self(self.0 | segment.mask())
     }
 
+    #[cfg(test)]
     pub const fn try_to_segment(self) -> Option<Segment> {
         if self.num_segments() != 1 {
             return None;
@@ -200,6 +505,30 @@ impl Digit {
         Segment::try_from_u8(self.0.trailing_zeros() as u8)
     }
 
+    /// The digit as three rows of three characters:
+    ///
+    /// ```text
+    ///  _
+    /// |_|
+    /// |_|
+    /// ```
+    pub fn render(self) -> [[char; 3]; 3] {
+        let seg = |segment, c| if self.is_set(segment) { c } else { ' ' };
+        [
+            [' ', seg(Segment::A, '_'), ' '],
+            [
+                seg(Segment::B, '|'),
+                seg(Segment::D, '_'),
+                seg(Segment::C, '|'),
+            ],
+            [
+                seg(Segment::E, '|'),
+                seg(Segment::G, '_'),
+                seg(Segment::F, '|'),
+            ],
+        ]
+    }
+
     pub fn transform(self, seg_map: &[(Segment, Segment)]) -> Self {
         let mut mapped = Self::EMPTY;
         for (old, new) in seg_map {
@@ -209,22 +538,25 @@ impl Digit {
         }
         mapped
     }
+}
 
-    pub const fn try_to_u8(self) -> Option<u8> {
-        match self {
-            Self::ZERO => Some(0),
-            Self::ONE => Some(1),
-            Self::TWO => Some(2),
-            Self::THREE => Some(3),
-            Self::FOUR => Some(4),
-            Self::FIVE => Some(5),
-            Self::SIX => Some(6),
-            Self::SEVEN => Some(7),
-            Self::EIGHT => Some(8),
-            Self::NINE => Some(9),
-            _ => None,
+/// Draws the digits side by side as ASCII art, three lines tall. If a `wiring` is given, each
+/// digit is treated as wires and shows the segments they light.
+pub fn render(digits: &[Digit], wiring: Option<Wiring>) -> String {
+    let digits = digits
+        .iter()
+        .map(|d| wiring.map_or(*d, |w| w.apply(*d)))
+        .collect::<Vec<_>>();
+    let mut lines = [String::new(), String::new(), String::new()];
+    for (idx, digit) in digits.iter().enumerate() {
+        if idx > 0 {
+            lines.iter_mut().for_each(|line| line.push(' '));
+        }
+        for (line, row) in lines.iter_mut().zip(digit.render()) {
+            line.extend(row);
         }
     }
+    lines.join("\n")
 }
 
 impl Default for Digit {
@@ -233,6 +565,18 @@ impl Default for Digit {
     }
 }
 
+/// Shows the lit segments as letters, like the input does.
+impl fmt::Display for Digit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in Segment::ALL {
+            if self.is_set(segment) {
+                segment.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl ops::BitAnd for Digit {
     type Output = Self;
 
@@ -278,6 +622,16 @@ pub enum Segment {
 }
 
 impl Segment {
+    pub const ALL: [Self; 7] = [
+        Self::A,
+        Self::B,
+        Self::C,
+        Self::D,
+        Self::E,
+        Self::F,
+        Self::G,
+    ];
+
     pub const fn mask(self) -> u8 {
         1 << (self as u8)
     }
@@ -309,6 +663,13 @@ impl Segment {
     }
 }
 
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = (b'a' + *self as u8) as char;
+        write!(f, "{}", c)
+    }
+}
+
 impl TryFrom<u8> for Segment {
     type Error = ();
 
@@ -347,4 +708,265 @@ mod tests {
         let digit = Digit(0b00010000);
         assert_eq!(Some(Segment::E), digit.try_to_segment());
     }
+
+    const HEX_A: Digit = Digit(0b00111111);
+    const HEX_B: Digit = Digit(0b01111010);
+    const HEX_C: Digit = Digit(0b01010011);
+    const HEX_D: Digit = Digit(0b01111100);
+    const HEX_E: Digit = Digit(0b01011011);
+    const HEX_F: Digit = Digit(0b00011011);
+
+    /// The decimal digits followed by `A`, `b`, `C`, `d`, `E` and `F`.
+    const HEX: [Digit; 16] = [
+        Digit::ZERO,
+        Digit::ONE,
+        Digit::TWO,
+        Digit::THREE,
+        Digit::FOUR,
+        Digit::FIVE,
+        Digit::SIX,
+        Digit::SEVEN,
+        Digit::EIGHT,
+        Digit::NINE,
+        HEX_A,
+        HEX_B,
+        HEX_C,
+        HEX_D,
+        HEX_E,
+        HEX_F,
+    ];
+
+    const EXAMPLE: &str =
+        "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+
+    fn entry(input: &str) -> Entry {
+        parse(input)[0]
+    }
+
+    fn digit(input: &str) -> Digit {
+        crate::utils::parse(super::digit, input)
+    }
+
+    #[test]
+    fn example_is_decoded() {
+        assert_eq!(Ok(5353), entry(EXAMPLE).get_output());
+    }
+
+    /// The patterns for `digits` on a display wired up by `wiring`.
+    fn scramble(wiring: Wiring, digits: &[Digit]) -> Vec<Digit> {
+        let inverse = wiring.inverse();
+        digits.iter().map(|d| inverse.apply(*d)).collect()
+    }
+
+    #[test]
+    fn example_wiring_is_unique() {
+        let entry = entry(EXAMPLE);
+        let solution = solve_wiring(&entry.signals, &Digit::DECIMAL);
+        let wiring = match solution {
+            Solution::Unique(wiring) => wiring,
+            other => panic!("expected a unique wiring, got {:?}", other),
+        };
+        assert_eq!(Segment::C, wiring.0[Segment::A as usize]);
+        assert_eq!(Segment::A, wiring.0[Segment::D as usize]);
+    }
+
+    #[test]
+    fn partial_patterns_are_ambiguous() {
+        // 1, 4 and 7 only pin down a, and which pairs of wires drive bd, cf and eg.
+        let patterns = scramble(Wiring::IDENTITY, &[Digit::ONE, Digit::FOUR, Digit::SEVEN]);
+        match solve_wiring(&patterns, &Digit::DECIMAL) {
+            Solution::Ambiguous(wirings) => {
+                assert_eq!(8, wirings.len());
+                assert!(wirings.contains(&Wiring::IDENTITY));
+                assert!(wirings.iter().all(|w| w.0[0] == Segment::A));
+            }
+            other => panic!("expected ambiguous wirings, got {:?}", other),
+        }
+
+        let eight = scramble(Wiring::IDENTITY, &[Digit::EIGHT]);
+        match solve_wiring(&eight, &Digit::DECIMAL) {
+            Solution::Ambiguous(wirings) => assert_eq!(5040, wirings.len()),
+            other => panic!("expected ambiguous wirings, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ambiguous_wirings_can_agree_on_output() {
+        // Every wiring that fits only ever sees 1s.
+        let ones = entry("ab ab ab ab ab ab ab ab ab ab | ab ba ab ab");
+        assert_eq!(Ok(1111), ones.decode(&Digit::DECIMAL));
+
+        // But a 6 segment pattern containing both wires of a 1 could be a 0 or a 9.
+        let zero_or_nine = entry("ab ab ab ab ab ab ab ab ab ab | abcdef ab ab ab");
+        assert!(zero_or_nine.decode(&Digit::DECIMAL).is_err());
+    }
+
+    #[test]
+    fn duplicate_signals_are_reported() {
+        let err = entry(&EXAMPLE.replace("dab", "ac"))
+            .get_output()
+            .unwrap_err();
+        let expected = DecodeError::WrongSignalCount {
+            num_segments: 2,
+            expected: 1,
+            signals: vec![digit("ac"), digit("ab")],
+        };
+        assert_eq!(expected, err);
+        assert_eq!(
+            "expected 1 signal(s) with 2 segments, found 2: ac, ab",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn contradictory_signals_are_reported() {
+        // Two different pairs of wires can't both drive the segments of a 1.
+        assert_eq!(
+            Solution::Contradictory,
+            solve_wiring(&[digit("ab"), digit("ac")], &Digit::DECIMAL)
+        );
+
+        let err = entry(&EXAMPLE.replace("gcdfa", "abcde"))
+            .get_output()
+            .unwrap_err();
+        let patterns = match &err {
+            DecodeError::Contradictory { patterns } => patterns.clone(),
+            other => panic!("expected a contradiction, got {:?}", other),
+        };
+        assert!(patterns.contains(&digit("abcde")));
+        assert_eq!(
+            Solution::Contradictory,
+            solve_wiring(&patterns, &Digit::DECIMAL)
+        );
+        for idx in 0..patterns.len() {
+            let mut rest = patterns.clone();
+            rest.remove(idx);
+            assert_ne!(
+                Solution::Contradictory,
+                solve_wiring(&rest, &Digit::DECIMAL)
+            );
+        }
+        assert!(err.to_string().starts_with("no wiring fits all of "));
+    }
+
+    #[test]
+    fn unexpected_signals_are_reported() {
+        let err = entry(&EXAMPLE.replace("ab |", "a |"))
+            .get_output()
+            .unwrap_err();
+        assert_eq!(DecodeError::UnexpectedSignal(digit("a")), err);
+    }
+
+    #[test]
+    fn unresolved_segments_are_reported() {
+        // With the signals all 1s, any wire lit in the 0 or 9 but not the 1 could drive segment a.
+        let input = "ab ab ab ab ab ab ab ab ab ab | abcdef ab ab ab";
+        let err = entry(input).decode(&Digit::DECIMAL).unwrap_err();
+        let expected = DecodeError::UnresolvedSegment {
+            segment: Segment::A,
+            wires: digit("cdef"),
+        };
+        assert_eq!(expected, err);
+        assert_eq!(
+            "couldn't tell which wire drives segment a, candidates are \"cdef\"",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn unknown_outputs_are_reported() {
+        let err = entry(&EXAMPLE.replace("cdbaf", "abcd"))
+            .get_output()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::UnknownOutput { position: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn hex_digits_are_decoded() {
+        let segments = [
+            Segment::E,
+            Segment::G,
+            Segment::A,
+            Segment::F,
+            Segment::B,
+            Segment::D,
+            Segment::C,
+        ];
+        let wiring = Wiring(segments);
+        let patterns = scramble(wiring, &HEX);
+        assert_eq!(Solution::Unique(wiring), solve_wiring(&patterns, &HEX));
+
+        let output = scramble(wiring, &[HEX_C, HEX_A, HEX_F, HEX_E]);
+        let entry = Entry {
+            signals: patterns[..10].try_into().unwrap(),
+            output: output.try_into().unwrap(),
+        };
+        assert_eq!(Ok(0xCAFE), entry.decode(&HEX));
+    }
+
+    #[test]
+    fn decode_all_reports_failing_entries() {
+        let input = [EXAMPLE, &EXAMPLE.replace("dab", "ac"), EXAMPLE].join("\n");
+        let outputs = decode_all(&parse(&input));
+        assert_eq!(Ok(5353), outputs[0]);
+        assert_eq!(Ok(5353), outputs[2]);
+        let err = outputs[1].clone().unwrap_err();
+        assert_eq!(1, err.index);
+        assert!(err.to_string().starts_with("entry 2: expected 1 signal(s)"));
+    }
+
+    #[test]
+    fn digits_are_rendered() {
+        let expected = [
+            " _       _   _       _   _   _   _   _ ",
+            "| |   |  _|  _| |_| |_  |_    | |_| |_|",
+            "|_|   | |_   _|   |  _| |_|   | |_|  _|",
+        ]
+        .join("\n");
+        assert_eq!(expected, render(&Digit::DECIMAL, None));
+
+        let hex = [
+            " _       _       _   _ ",
+            "|_| |_  |    _| |_  |_ ",
+            "| | |_| |_  |_| |_  |  ",
+        ]
+        .join("\n");
+        assert_eq!(hex, render(&HEX[10..], None));
+    }
+
+    #[test]
+    fn wired_digits_are_rendered_unscrambled() {
+        let entry = entry(EXAMPLE);
+        let wiring = match solve_wiring(&entry.signals, &Digit::DECIMAL) {
+            Solution::Unique(wiring) => wiring,
+            other => panic!("expected a unique wiring, got {:?}", other),
+        };
+        let expected = render(
+            &[Digit::FIVE, Digit::THREE, Digit::FIVE, Digit::THREE],
+            None,
+        );
+        assert_eq!(expected, render(&entry.output, Some(wiring)));
+    }
+
+    #[test]
+    fn generated_entries_decode_to_their_output() {
+        let mut rng = crate::utils::Rng::new(0x5eed);
+        for _ in 0..50 {
+            let wiring = Wiring::random(&mut rng);
+            let output = [0; 4].map(|_| rng.below(10) as u8);
+            let entry = Entry::generate(wiring, output, &mut rng).unwrap();
+
+            assert_eq!(entry, super::parse(&entry.to_string())[0]);
+            assert_eq!(
+                Solution::Unique(wiring),
+                solve_wiring(&entry.signals, &Digit::DECIMAL)
+            );
+            let expected = output.iter().fold(0, |acc, d| acc * 10 + *d as u64);
+            assert_eq!(Ok(expected), entry.get_output());
+        }
+        assert_ne!(Wiring::random(&mut rng), Wiring::random(&mut rng));
+    }
 }
@@ -1,154 +1,325 @@
-use super::utils::{Vec2d, Vec2dBuilder};
-use nom::{combinator::map, IResult};
+use super::utils::{DisjointSet, Point, Vec2d};
+use itertools::Either;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    error::Error,
+    fmt,
+    io::{self, Write},
+};
 
-pub fn star_1(data: String) {
+pub fn star_1(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let rules = rules(args)?;
     let heights = parse(&data);
+    let risk_level_sum = low_points(&heights, rules)
+        .iter()
+        .map(|p| (p.height as u64) + 1)
+        .sum::<u64>();
+    println!("{}", risk_level_sum);
+    Ok(())
+}
 
-    let mut risk_level_sum: u64 = 0;
-    for coord in heights.all_coords() {
-        let center = heights[coord];
-        let is_basin = heights
-            .neighbor_coords(coord)
-            .map(|c| heights[c])
-            .all(|h| h > center);
+pub fn star_2(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let rules = rules(args)?;
+    let heights = parse(&data);
+    let basins = Basins::new(&heights, rules);
+    let product = basins.sizes.iter().take(3).product::<usize>();
+    println!("{}", product);
+    Ok(())
+}
+
+/// Prints every low point with its height and the size of its basin as CSV.
+pub fn low_points_csv(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let rules = rules(args)?;
+    let points = low_points(&parse(&data), rules);
+    write_csv(&points, io::stdout().lock())?;
+    Ok(())
+}
 
-        if is_basin {
-            risk_level_sum += (center as u64) + 1;
+/// Parses the rules given after the input file: `diagonal` to connect cells diagonally,
+/// `plateaus` to allow low points on plateaus, and `wall=<height>` for the lowest wall height.
+fn rules(args: &[String]) -> Result<BasinRules, UnknownRule> {
+    let mut rules = BasinRules::default();
+    for arg in args {
+        match arg.as_str() {
+            "diagonal" => rules.connectivity = Connectivity::Eight,
+            "plateaus" => rules.plateau_low_points = true,
+            _ => {
+                rules.wall_height = arg
+                    .strip_prefix("wall=")
+                    .and_then(|height| height.parse().ok())
+                    .ok_or_else(|| UnknownRule(arg.clone()))?
+            }
         }
     }
-    println!("{}", risk_level_sum);
+    Ok(rules)
 }
 
-pub fn star_2(data: String) {
-    use std::collections::{HashMap, HashSet};
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UnknownRule(String);
 
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-    enum Cell {
-        Peak,
-        Unset,
-        Basin(usize),
+impl fmt::Display for UnknownRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` isn't a basin rule, expected `diagonal`, `plateaus` or `wall=<height>`",
+            self.0
+        )
     }
+}
 
-    impl Cell {
-        fn basin(self) -> Option<usize> {
-            match self {
-                Self::Peak | Self::Unset => None,
-                Self::Basin(b) => Some(b),
-            }
-        }
-    }
+impl Error for UnknownRule {}
 
-    let mut heights = parse(&data).map(|h| if h >= 9 { Cell::Peak } else { Cell::Unset });
-    let mut basin_counts = Vec::new();
-    let mut joins = HashMap::new();
-    let mut rev_joins = HashMap::new();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BasinRules {
+    /// Cells this high or higher are walls, which don't belong to any basin.
+    wall_height: u8,
+    connectivity: Connectivity,
+    /// Whether a cell can be a low point when some of its neighbors are the same height.
+    plateau_low_points: bool,
+}
 
-    for coord in heights.all_coords() {
-        if heights[coord] == Cell::Peak {
-            continue;
-        }
-        let up = heights
-            .up(coord)
-            .and_then(|c| heights.get(c))
-            .and_then(|c| c.basin());
-        let left = heights
-            .left(coord)
-            .and_then(|c| heights.get(c))
-            .and_then(|c| c.basin());
-        let basin = match (up, left) {
-            (Some(x), Some(y)) if x == y => x,
-            (Some(x), Some(y)) => {
-                let (lesser, greater) = if x < y { (x, y) } else { (y, x) };
-                joins
-                    .entry(lesser)
-                    .or_insert_with(HashSet::new)
-                    .insert(greater);
-                rev_joins
-                    .entry(greater)
-                    .or_insert_with(HashSet::new)
-                    .insert(lesser);
-                lesser
-            }
-            (Some(x), None) | (None, Some(x)) => x,
-            (None, None) => basin_counts.len(),
-        };
-        while basin_counts.len() <= basin {
-            basin_counts.push(0);
-        }
-        basin_counts[basin] += 1;
-        heights[coord] = Cell::Basin(basin);
+impl BasinRules {
+    fn is_wall(self, height: u8) -> bool {
+        height >= self.wall_height
     }
+}
 
-    fn sum_of_joins(
-        basin_counts: &[usize],
-        joins: &HashMap<usize, HashSet<usize>>,
-        basin: usize,
-        already_counted: &mut HashSet<usize>,
-    ) -> usize {
-        if already_counted.contains(&basin) {
-            return 0;
+impl Default for BasinRules {
+    fn default() -> Self {
+        Self {
+            wall_height: 9,
+            connectivity: Connectivity::Four,
+            plateau_low_points: false,
         }
-        let mut count = basin_counts[basin];
-        already_counted.insert(basin);
-        if let Some(join_set) = joins.get(&basin) {
-            for b in join_set {
-                count += sum_of_joins(basin_counts, joins, *b, already_counted);
-            }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Connectivity {
+    /// Cells connect to the cells above, below, left and right of them.
+    Four,
+    /// Cells also connect diagonally.
+    Eight,
+}
+
+impl Connectivity {
+    fn neighbors<T>(
+        self,
+        grid: &Vec2d<T>,
+        coord: (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let point = Point::from(coord);
+        match self {
+            Self::Four => Either::Left(point.neighbors()),
+            Self::Eight => Either::Right(point.all_neighbors()),
         }
-        count
+        .map(|p| (p.x(), p.y()))
+        .filter(|&coord| grid.get(coord).is_some())
     }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LowPoint {
+    coord: (usize, usize),
+    height: u8,
+    basin_size: usize,
+}
 
-    let mut basin_sizes = (0..basin_counts.len())
-        .filter(|b| rev_joins.get(b).is_none())
-        .map(|b| {
-            let mut already_counted = HashSet::new();
-            sum_of_joins(&basin_counts, &joins, b, &mut already_counted)
+fn low_points(heights: &Vec2d<u8>, rules: BasinRules) -> Vec<LowPoint> {
+    let basins = Basins::new(heights, rules);
+    heights
+        .all_coords()
+        .filter(|coord| is_low_point(heights, *coord, rules))
+        .map(|coord| LowPoint {
+            coord,
+            height: heights[coord],
+            basin_size: basins.labels[coord].map_or(0, |label| basins.sizes[label]),
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
 
-    basin_sizes.sort_unstable();
-    basin_sizes.reverse();
+fn write_csv<W: Write>(points: &[LowPoint], mut out: W) -> io::Result<()> {
+    writeln!(out, "col,row,height,basin_size")?;
+    for point in points {
+        let (col, row) = point.coord;
+        writeln!(out, "{},{},{},{}", col, row, point.height, point.basin_size)?;
+    }
+    Ok(())
+}
 
-    let product = basin_sizes.iter().take(3).product::<usize>();
-    println!("{}", product);
+fn is_low_point(heights: &Vec2d<u8>, coord: (usize, usize), rules: BasinRules) -> bool {
+    let center = heights[coord];
+    !rules.is_wall(center)
+        && rules
+            .connectivity
+            .neighbors(heights, coord)
+            .map(|c| heights[c])
+            .all(|h| h > center || (rules.plateau_low_points && h == center))
 }
 
-fn parse(input: &str) -> Vec2d<u8> {
-    super::utils::parse(vec2d_u8, input)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Basins {
+    /// The basin each cell belongs to, or `None` for walls.
+    labels: Vec2d<Option<usize>>,
+    /// The size of each basin, indexed by label, from largest to smallest.
+    sizes: Vec<usize>,
 }
 
-fn vec2d_u8(input: &str) -> IResult<&str, Vec2d<u8>> {
-    use nom::multi::fold_many0;
+impl Basins {
+    fn new(heights: &Vec2d<u8>, rules: BasinRules) -> Self {
+        let width = heights.width();
+        let idx = |(col, row): (usize, usize)| row * width + col;
+        let is_basin = |coord| !rules.is_wall(heights[coord]);
 
-    map(
-        fold_many0(digit_or_newline, Vec2dBuilder::new, |mut b, d| {
-            match d {
-                DigitOrNewline::Digit(x) => b.push(x),
-                DigitOrNewline::Newline => b.finish_row(),
+        let mut cells = DisjointSet::new(width * heights.height());
+        for coord in heights.all_coords().filter(|c| is_basin(*c)) {
+            for neighbor in rules.connectivity.neighbors(heights, coord) {
+                if is_basin(neighbor) {
+                    cells.union(idx(coord), idx(neighbor));
+                }
             }
-            b
-        }),
-        Vec2dBuilder::build,
-    )(input)
+        }
+
+        let mut roots = heights
+            .all_coords()
+            .filter(|c| is_basin(*c))
+            .map(|c| cells.find(idx(c)))
+            .collect::<Vec<_>>();
+        roots.sort_unstable();
+        roots.dedup();
+        roots.sort_by_key(|root| Reverse(cells.size(*root)));
+
+        let root_labels = roots
+            .iter()
+            .enumerate()
+            .map(|(label, root)| (*root, label))
+            .collect::<HashMap<_, _>>();
+        let sizes = roots.iter().map(|root| cells.size(*root)).collect();
+
+        let mut labels = Vec2d::repeat(None, width, heights.height());
+        for coord in heights.all_coords().filter(|c| is_basin(*c)) {
+            labels[coord] = Some(root_labels[&cells.find(idx(coord))]);
+        }
+
+        Self { labels, sizes }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum DigitOrNewline {
-    Digit(u8),
-    Newline,
+fn parse(input: &str) -> Vec2d<u8> {
+    super::utils::parse(super::utils::digit_grid, input)
 }
 
-fn digit_or_newline(input: &str) -> IResult<&str, DigitOrNewline> {
-    use nom::{branch::alt, character::complete::line_ending, combinator::value};
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    alt((
-        map(digit, DigitOrNewline::Digit),
-        value(DigitOrNewline::Newline, line_ending),
-    ))(input)
-}
+    #[test]
+    fn basins_are_labelled_by_size() {
+        let heights = parse("2199943210\n3987894921\n9856789892\n8767896789\n9899965678");
+        let basins = Basins::new(&heights, BasinRules::default());
+        assert_eq!(vec![14, 9, 9, 3], basins.sizes);
+        assert_eq!(Some(3), basins.labels[(0, 0)]);
+        assert_eq!(None, basins.labels[(2, 0)]);
+        assert_eq!(Some(0), basins.labels[(2, 2)]);
+        assert_eq!(basins.labels[(9, 0)], basins.labels[(6, 1)]);
+        assert_ne!(basins.labels[(9, 0)], basins.labels[(9, 4)]);
+    }
+
+    #[test]
+    fn low_points_report_basins() {
+        let heights = parse("2199943210\n3987894921\n9856789892\n8767896789\n9899965678");
+        let points = low_points(&heights, BasinRules::default());
+        let expected = [
+            ((1, 0), 1, 3),
+            ((9, 0), 0, 9),
+            ((2, 2), 5, 14),
+            ((6, 4), 5, 9),
+        ]
+        .map(|(coord, height, basin_size)| LowPoint {
+            coord,
+            height,
+            basin_size,
+        });
+        assert_eq!(&expected[..], &points[..]);
+    }
 
-fn digit(input: &str) -> IResult<&str, u8> {
-    use nom::{character::complete::anychar, combinator::map_opt};
+    #[test]
+    fn diagonal_flow_joins_basins() {
+        let heights = parse("191\n919\n191");
+        let four = Basins::new(&heights, BasinRules::default());
+        assert_eq!(vec![1; 5], four.sizes);
 
-    map_opt(anychar, |c| c.to_digit(10).map(|x| x as u8))(input)
+        let rules = BasinRules {
+            connectivity: Connectivity::Eight,
+            ..BasinRules::default()
+        };
+        let eight = Basins::new(&heights, rules);
+        assert_eq!(vec![5], eight.sizes);
+        assert!(low_points(&heights, rules).is_empty());
+        let plateaus = BasinRules {
+            plateau_low_points: true,
+            ..rules
+        };
+        assert_eq!(5, low_points(&heights, plateaus).len());
+    }
+
+    #[test]
+    fn wall_height_is_configurable() {
+        let heights = parse("1571\n2682");
+        let rules = BasinRules {
+            wall_height: 5,
+            ..BasinRules::default()
+        };
+        assert_eq!(vec![2, 2], Basins::new(&heights, rules).sizes);
+        assert_eq!(vec![8], Basins::new(&heights, BasinRules::default()).sizes);
+    }
+
+    #[test]
+    fn plateaus_can_be_low_points() {
+        let heights = parse("113\n224");
+        assert!(low_points(&heights, BasinRules::default()).is_empty());
+
+        let rules = BasinRules {
+            plateau_low_points: true,
+            ..BasinRules::default()
+        };
+        let coords = low_points(&heights, rules)
+            .iter()
+            .map(|p| p.coord)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(0, 0), (1, 0)], coords);
+    }
+
+    #[test]
+    fn rules_are_parsed() {
+        let args =
+            |args: &[&str]| rules(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>());
+        assert_eq!(Ok(BasinRules::default()), args(&[]));
+        let expected = BasinRules {
+            wall_height: 5,
+            connectivity: Connectivity::Eight,
+            plateau_low_points: true,
+        };
+        assert_eq!(Ok(expected), args(&["plateaus", "wall=5", "diagonal"]));
+        for arg in ["flat", "wall=", "wall=high", "wall=256"] {
+            let err = args(&[arg]).unwrap_err();
+            assert_eq!(
+                format!(
+                    "`{}` isn't a basin rule, expected `diagonal`, `plateaus` or `wall=<height>`",
+                    arg
+                ),
+                err.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn low_points_are_written_as_csv() {
+        let heights = parse("2199943210\n3987894921\n9856789892\n8767896789\n9899965678");
+        let mut out = Vec::new();
+        write_csv(&low_points(&heights, BasinRules::default()), &mut out).unwrap();
+        let expected = "col,row,height,basin_size\n1,0,1,3\n9,0,0,9\n2,2,5,14\n6,4,5,9\n";
+        assert_eq!(expected, String::from_utf8(out).unwrap());
+    }
 }
@@ -1,151 +1,819 @@
-use nom::IResult;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    error::Error,
+    fmt,
+    str::FromStr,
+};
 
-pub fn star_1(data: String) {
-    let lines = parse(&data);
-    let mut total_score = 0;
-    for line in lines {
+pub fn star_1(data: String) -> Result<(), Box<dyn Error>> {
+    let delims = Delims::default();
+    let scores = Scores::default();
+    let total_score = data
+        .lines()
+        .filter_map(|line| scores.corrupted(&delims.check(line)))
+        .sum::<Result<u64, _>>()?;
+    println!("{}", total_score);
+    Ok(())
+}
+
+pub fn star_2(data: String) -> Result<(), Box<dyn Error>> {
+    let delims = Delims::default();
+    let scores = Scores::default();
+    let mut line_scores = data
+        .lines()
+        .filter_map(|line| scores.incomplete(&delims.check(line)))
+        .collect::<Result<Vec<_>, _>>()?;
+    line_scores.sort_unstable();
+    println!("{}", line_scores[line_scores.len() / 2]);
+    Ok(())
+}
+
+/// Prints the smallest fix for each corrupted line. The target can be given after the file name,
+/// and is `balanced` by default.
+pub fn repair(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let target = match args.first() {
+        Some(arg) => arg.parse()?,
+        None => RepairTarget::Balanced,
+    };
+    let delims = Delims::default();
+    for (idx, line) in data.lines().enumerate() {
+        let status = delims.check(line);
+        if let LineStatus::Corrupted { .. } = status {
+            let repair = delims.repair(line, target);
+            let edits = repair
+                .edits
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>();
+            println!("line {}: {}; fix: {}", idx + 1, status, edits.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// The pairs of characters that open and close a chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delims {
+    pairs: Vec<(char, char)>,
+}
+
+impl Delims {
+    pub fn new(pairs: Vec<(char, char)>) -> Self {
+        Self { pairs }
+    }
+
+    fn delim(&self, c: char) -> Option<Delim> {
+        self.pairs
+            .iter()
+            .enumerate()
+            .find_map(|(idx, (open, close))| {
+                let ty = if c == *open {
+                    DelimTy::Open
+                } else if c == *close {
+                    DelimTy::Close
+                } else {
+                    return None;
+                };
+                Some(Delim {
+                    shape: DelimShape(idx),
+                    ty,
+                })
+            })
+    }
+
+    fn open(&self, shape: DelimShape) -> char {
+        self.pairs[shape.0].0
+    }
+
+    fn close(&self, shape: DelimShape) -> char {
+        self.pairs[shape.0].1
+    }
+
+    /// Checks that every chunk in the line is closed by the right character. Characters that
+    /// aren't delimiters are skipped.
+    pub fn check(&self, line: &str) -> LineStatus {
         let mut stack = Vec::new();
-        for c in line {
-            if c.ty == DelimTy::Open {
-                stack.push(c);
-            } else {
-                let t = stack.pop().unwrap();
-                if c.shape != t.shape {
-                    total_score += c.shape.corrupted_score();
-                    break;
-                }
+        for (column, c) in line.chars().enumerate() {
+            let delim = match self.delim(c) {
+                Some(delim) => delim,
+                None => continue,
+            };
+            match delim.ty {
+                DelimTy::Open => stack.push(delim.shape),
+                DelimTy::Close => match stack.pop() {
+                    Some(shape) if shape == delim.shape => {}
+                    open => {
+                        return LineStatus::Corrupted {
+                            column,
+                            expected: open.map(|shape| self.close(shape)),
+                            found: c,
+                        }
+                    }
+                },
             }
         }
+
+        if stack.is_empty() {
+            LineStatus::Valid
+        } else {
+            let completion = stack.into_iter().rev().map(|s| self.close(s)).collect();
+            LineStatus::Incomplete { completion }
+        }
     }
-    println!("{}", total_score);
 }
 
-pub fn star_2(data: String) {
-    let lines = parse(&data);
-    let mut scores = Vec::new();
-    'outer: for line in lines {
-        let mut stack = Vec::new();
-        for c in line {
-            if c.ty == DelimTy::Open {
-                stack.push(c);
-            } else {
-                let t = stack.pop().unwrap();
-                if c.shape != t.shape {
-                    continue 'outer;
+impl Delims {
+    /// Finds the fewest single character edits that get the line to `target`.
+    ///
+    /// This is an A* search over how far through the line we are and what the stack looks like.
+    /// Reading a character costs nothing, but can only be done if it doesn't corrupt the line;
+    /// every edit costs one. Ignoring shapes, the rest of the line leaves some closes with nothing
+    /// to close and, for [`RepairTarget::Balanced`], some chunks open at the end. An edit can fix
+    /// two of those at most, so half of them is a lower bound on the edits still needed. For
+    /// [`RepairTarget::Balanced`] the bound from [`Self::closing_costs`] is used as well, which
+    /// only ignores the shapes on the stack.
+    pub fn repair(&self, line: &str, target: RepairTarget) -> Repair {
+        let chars = line.chars().collect::<Vec<_>>();
+        let delims = chars.iter().map(|c| self.delim(*c)).collect::<Vec<_>>();
+
+        // How much deeper the stack gets from reading the rest of the line, ignoring shapes, and
+        // the lowest it gets relative to where it started.
+        let mut depth_changes = vec![(0, 0); chars.len() + 1];
+        for idx in (0..chars.len()).rev() {
+            let step = match delims[idx] {
+                Some(Delim {
+                    ty: DelimTy::Open, ..
+                }) => 1,
+                Some(Delim {
+                    ty: DelimTy::Close, ..
+                }) => -1,
+                None => 0,
+            };
+            let (change, lowest) = depth_changes[idx + 1];
+            depth_changes[idx] = (change + step, (step + lowest).min(0));
+        }
+        let closing_costs = match target {
+            RepairTarget::Completable => Vec::new(),
+            RepairTarget::Balanced => Self::closing_costs(&delims),
+        };
+
+        let mut stacks = Stacks::new();
+        let estimate = |state: RepairState, stacks: &Stacks| {
+            let depth = stacks.len(state.stack);
+            let (change, lowest) = depth_changes[state.column];
+            let unmatched_closes = (-(depth as i64 + lowest)).max(0);
+            let left_open = match target {
+                RepairTarget::Completable => 0,
+                RepairTarget::Balanced => depth as i64 + change + unmatched_closes,
+            };
+            let bound = ((unmatched_closes + left_open) as usize).div_ceil(2);
+            closing_costs
+                .get(state.column)
+                .and_then(|costs| costs.get(depth))
+                .map_or(bound, |cost| bound.max(*cost))
+        };
+
+        let start = RepairState {
+            column: 0,
+            stack: StackId::EMPTY,
+        };
+        let mut nodes = vec![(start, None)];
+        let mut costs = HashMap::from([(start, 0)]);
+        // Of the states with the same estimate, the ones with more edits made are expanded first,
+        // since less of their estimate is a guess.
+        let mut queue = BinaryHeap::from([Reverse((estimate(start, &stacks), Reverse(0), 0))]);
+
+        while let Some(Reverse((_, Reverse(cost), node))) = queue.pop() {
+            let state = nodes[node].0;
+            if costs[&state] < cost {
+                continue;
+            }
+            if state.column == chars.len()
+                && (target == RepairTarget::Completable || state.stack == StackId::EMPTY)
+            {
+                return Repair::new(&chars, Self::edits(&nodes, node));
+            }
+
+            let mut next = Vec::new();
+            let column = state.column;
+            let stack = state.stack;
+
+            // Characters that could be inserted or swapped in without corrupting the line.
+            let candidates = stacks
+                .top(stack)
+                .map(|shape| Delim {
+                    shape,
+                    ty: DelimTy::Close,
+                })
+                .into_iter()
+                .chain((0..self.pairs.len()).map(|idx| Delim {
+                    shape: DelimShape(idx),
+                    ty: DelimTy::Open,
+                }));
+
+            for delim in candidates {
+                let stack = stacks.push(stack, delim).unwrap();
+                let c = self.char(delim);
+                next.push((column, stack, Some(Edit::Insert { column, c })));
+                if column < chars.len() && delims[column].is_some() && chars[column] != c {
+                    next.push((column + 1, stack, Some(Edit::Replace { column, c })));
+                }
+            }
+            if column < chars.len() {
+                match delims[column] {
+                    Some(delim) => {
+                        if let Some(stack) = stacks.push(stack, delim) {
+                            next.push((column + 1, stack, None));
+                        }
+                        next.push((column + 1, stack, Some(Edit::Delete { column })));
+                    }
+                    None => next.push((column + 1, stack, None)),
+                }
+            }
+
+            for (column, stack, edit) in next {
+                let cost = cost + edit.is_some() as usize;
+                let state = RepairState { column, stack };
+                if costs.get(&state).is_some_and(|c| *c <= cost) {
+                    continue;
                 }
+                costs.insert(state, cost);
+                let estimate = cost + estimate(state, &stacks);
+                queue.push(Reverse((estimate, Reverse(cost), nodes.len())));
+                nodes.push((state, Some((node, edit))));
+            }
+        }
+
+        unreachable!("every line can be repaired by deleting all of it and inserting closes")
+    }
+
+    /// For each column and stack depth, the fewest edits that close every chunk by the end of the
+    /// line, as if any closing character could close the chunks already on the stack.
+    fn closing_costs(delims: &[Option<Delim>]) -> Vec<Vec<usize>> {
+        let len = delims.len();
+        let pair_cost = |first: Delim, second: Delim| match (first.ty, second.ty) {
+            (DelimTy::Open, DelimTy::Close) => usize::from(first.shape != second.shape),
+            (DelimTy::Open, DelimTy::Open) | (DelimTy::Close, DelimTy::Close) => 1,
+            (DelimTy::Close, DelimTy::Open) => 2,
+        };
+
+        // The fewest edits that balance `delims[start..end]` on its own. The first delimiter is
+        // either deleted or given a new partner, for one edit, or paired up with a later one.
+        let mut balanced = vec![vec![0; len + 1]; len + 1];
+        for start in (0..len).rev() {
+            for end in start + 1..=len {
+                balanced[start][end] = match delims[start] {
+                    None => balanced[start + 1][end],
+                    Some(first) => (start + 1..end)
+                        .filter_map(|mid| {
+                            let second = delims[mid]?;
+                            Some(
+                                pair_cost(first, second)
+                                    + balanced[start + 1][mid]
+                                    + balanced[mid + 1][end],
+                            )
+                        })
+                        .fold(balanced[start + 1][end] + 1, usize::min),
+                };
+            }
+        }
+
+        // The rest of the line has to be balanced runs separated by one close per chunk on the
+        // stack. Each close is either already in the line, swapped in, or inserted.
+        let mut closing = vec![vec![0; len + 1]; len + 1];
+        for start in (0..=len).rev() {
+            closing[start][0] = balanced[start][len];
+            for depth in 1..=len {
+                closing[start][depth] = (start..len)
+                    .filter_map(|mid| {
+                        let delim = delims[mid]?;
+                        Some(
+                            balanced[start][mid]
+                                + usize::from(delim.ty == DelimTy::Open)
+                                + closing[mid + 1][depth - 1],
+                        )
+                    })
+                    .fold(closing[start][depth - 1] + 1, usize::min);
             }
         }
-        let score = stack
-            .into_iter()
-            .rev()
-            .map(|c| c.shape.incomplete_score())
-            .fold(0, |acc, s| acc * 5 + s);
-        scores.push(score);
+        closing
     }
-    scores.sort_unstable();
-    println!("{}", scores[scores.len() / 2]);
+
+    fn char(&self, delim: Delim) -> char {
+        match delim.ty {
+            DelimTy::Open => self.open(delim.shape),
+            DelimTy::Close => self.close(delim.shape),
+        }
+    }
+
+    /// The edits along the path to `node`, in order.
+    fn edits(nodes: &[RepairNode], node: usize) -> Vec<Edit> {
+        let mut edits = Vec::new();
+        let mut node = node;
+        while let Some((parent, edit)) = nodes[node].1 {
+            edits.extend(edit);
+            node = parent;
+        }
+        edits.reverse();
+        edits
+    }
+}
+
+/// A search state, with the node it was reached from and the edit made to get there, if any.
+type RepairNode = (RepairState, Option<(usize, Option<Edit>)>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct RepairState {
+    column: usize,
+    stack: StackId,
 }
 
-fn parse(input: &str) -> Vec<Vec<Delim>> {
-    super::utils::parse(lines, input)
+/// A stack in [`Stacks`]. Equal stacks always have the same id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct StackId(usize);
+
+impl StackId {
+    const EMPTY: Self = Self(0);
 }
 
-fn lines(input: &str) -> IResult<&str, Vec<Vec<Delim>>> {
-    super::utils::lines(line)(input)
+/// The stacks seen by a search, as linked lists that share their bottoms, so a push only stores
+/// the new top and comparing stacks only compares ids.
+#[derive(Debug, Clone)]
+struct Stacks {
+    /// The top shape, the rest of the stack and the length of each stack, indexed by id.
+    entries: Vec<(Option<DelimShape>, StackId, usize)>,
+    ids: HashMap<(StackId, DelimShape), StackId>,
 }
 
-fn line(input: &str) -> IResult<&str, Vec<Delim>> {
-    use nom::multi::many1;
+impl Stacks {
+    fn new() -> Self {
+        Self {
+            entries: vec![(None, StackId::EMPTY, 0)],
+            ids: HashMap::new(),
+        }
+    }
+
+    fn top(&self, stack: StackId) -> Option<DelimShape> {
+        self.entries[stack.0].0
+    }
+
+    fn len(&self, stack: StackId) -> usize {
+        self.entries[stack.0].2
+    }
 
-    many1(delim)(input)
+    /// The stack after `delim` is read, or `None` if it closes the wrong chunk.
+    fn push(&mut self, stack: StackId, delim: Delim) -> Option<StackId> {
+        match delim.ty {
+            DelimTy::Open => {
+                let len = self.len(stack) + 1;
+                let entries = &mut self.entries;
+                let id = *self.ids.entry((stack, delim.shape)).or_insert_with(|| {
+                    entries.push((Some(delim.shape), stack, len));
+                    StackId(entries.len() - 1)
+                });
+                Some(id)
+            }
+            DelimTy::Close => {
+                let (top, rest, _) = self.entries[stack.0];
+                (top? == delim.shape).then_some(rest)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Delim {
-    shape: DelimShape,
-    ty: DelimTy,
+pub enum RepairTarget {
+    /// No character closes the wrong chunk, though some chunks may be left open.
+    Completable,
+    /// Every chunk is closed as well.
+    Balanced,
 }
 
-impl TryFrom<char> for Delim {
-    type Error = ();
-
-    fn try_from(c: char) -> Result<Self, Self::Error> {
-        use DelimShape::{Angle, Curly, Paren, Square};
-        use DelimTy::{Close, Open};
-        match c {
-            '(' => Ok(Delim {
-                shape: Paren,
-                ty: Open,
-            }),
-            ')' => Ok(Delim {
-                shape: Paren,
-                ty: Close,
-            }),
-            '[' => Ok(Delim {
-                shape: Square,
-                ty: Open,
-            }),
-            ']' => Ok(Delim {
-                shape: Square,
-                ty: Close,
-            }),
-            '{' => Ok(Delim {
-                shape: Curly,
-                ty: Open,
-            }),
-            '}' => Ok(Delim {
-                shape: Curly,
-                ty: Close,
-            }),
-            '<' => Ok(Delim {
-                shape: Angle,
-                ty: Open,
-            }),
-            '>' => Ok(Delim {
-                shape: Angle,
-                ty: Close,
-            }),
-            _ => Err(()),
-        }
-    }
-}
-
-fn delim(input: &str) -> IResult<&str, Delim> {
-    use nom::{character::complete::anychar, combinator::map_res};
-
-    map_res(anychar, Delim::try_from)(input)
+impl FromStr for RepairTarget {
+    type Err = UnknownTarget;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "completable" => Ok(Self::Completable),
+            "balanced" => Ok(Self::Balanced),
+            _ => Err(UnknownTarget(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownTarget(String);
+
+impl fmt::Display for UnknownTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` isn't a repair target, expected `completable` or `balanced`",
+            self.0
+        )
+    }
 }
 
+impl Error for UnknownTarget {}
+
+/// A single character edit. Columns count from 0 and refer to the original line, so an insert at
+/// `column` goes before the character that was there.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum DelimShape {
-    Paren,
-    Square,
-    Curly,
-    Angle,
+pub enum Edit {
+    Insert { column: usize, c: char },
+    Delete { column: usize },
+    Replace { column: usize, c: char },
 }
 
-impl DelimShape {
-    fn corrupted_score(self) -> u64 {
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Paren => 3,
-            Self::Square => 57,
-            Self::Curly => 1197,
-            Self::Angle => 25137,
+            Self::Insert { column, c } => write!(f, "insert {} at column {}", c, column + 1),
+            Self::Delete { column } => write!(f, "delete column {}", column + 1),
+            Self::Replace { column, c } => write!(f, "replace column {} with {}", column + 1, c),
         }
     }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Repair {
+    pub edits: Vec<Edit>,
+    /// The line with the edits made.
+    pub line: String,
+}
 
-    fn incomplete_score(self) -> u64 {
+impl Repair {
+    fn new(chars: &[char], edits: Vec<Edit>) -> Self {
+        let mut line = String::new();
+        let mut edits_iter = edits.iter().peekable();
+        for column in 0..=chars.len() {
+            let mut keep = column < chars.len();
+            while let Some(edit) = edits_iter.next_if(|e| e.column() == column) {
+                match edit {
+                    Edit::Insert { c, .. } => line.push(*c),
+                    Edit::Delete { .. } => keep = false,
+                    Edit::Replace { c, .. } => {
+                        line.push(*c);
+                        keep = false;
+                    }
+                }
+            }
+            if keep {
+                line.push(chars[column]);
+            }
+        }
+        Self { edits, line }
+    }
+}
+
+impl Edit {
+    fn column(self) -> usize {
+        match self {
+            Self::Insert { column, .. }
+            | Self::Delete { column }
+            | Self::Replace { column, .. } => column,
+        }
+    }
+}
+
+impl Default for Delims {
+    fn default() -> Self {
+        Self::new(vec![('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')])
+    }
+}
+
+/// Points for each closing character, depending on whether it corrupts a line or completes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scores {
+    corrupted: HashMap<char, u64>,
+    incomplete: HashMap<char, u64>,
+}
+
+impl Scores {
+    pub fn new(corrupted: HashMap<char, u64>, incomplete: HashMap<char, u64>) -> Self {
+        Self {
+            corrupted,
+            incomplete,
+        }
+    }
+
+    /// The score for the first illegal character, if the line is corrupted.
+    pub fn corrupted(&self, status: &LineStatus) -> Option<Result<u64, MissingScore>> {
+        match status {
+            LineStatus::Corrupted { found, .. } => Some(
+                self.corrupted
+                    .get(found)
+                    .copied()
+                    .ok_or(MissingScore(*found)),
+            ),
+            _ => None,
+        }
+    }
+
+    /// The score for the completion string, if the line is incomplete.
+    pub fn incomplete(&self, status: &LineStatus) -> Option<Result<u64, MissingScore>> {
+        match status {
+            LineStatus::Incomplete { completion } => {
+                Some(completion.chars().try_fold(0, |acc, c| {
+                    let score = self.incomplete.get(&c).ok_or(MissingScore(c))?;
+                    Ok(acc * 5 + score)
+                }))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A closing character that [`Scores`] has no points for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MissingScore(pub char);
+
+impl fmt::Display for MissingScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no score given for {}", self.0)
+    }
+}
+
+impl Error for MissingScore {}
+
+impl Default for Scores {
+    fn default() -> Self {
+        Self::new(
+            HashMap::from([(')', 3), (']', 57), ('}', 1197), ('>', 25137)]),
+            HashMap::from([(')', 1), (']', 2), ('}', 3), ('>', 4)]),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LineStatus {
+    /// Every chunk is closed.
+    Valid,
+    /// The character at `column` (counting from 0) closes the wrong chunk. `expected` is the
+    /// character that would have closed the open chunk, if there was one.
+    Corrupted {
+        column: usize,
+        expected: Option<char>,
+        found: char,
+    },
+    /// The line ends with chunks still open, which `completion` would close.
+    Incomplete { completion: String },
+}
+
+impl fmt::Display for LineStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Paren => 1,
-            Self::Square => 2,
-            Self::Curly => 3,
-            Self::Angle => 4,
+            Self::Valid => write!(f, "valid"),
+            Self::Corrupted {
+                column,
+                expected: Some(expected),
+                found,
+            } => write!(
+                f,
+                "column {}: expected {}, but found {} instead",
+                column + 1,
+                expected,
+                found
+            ),
+            Self::Corrupted {
+                column,
+                expected: None,
+                found,
+            } => write!(f, "column {}: unexpected {}", column + 1, found),
+            Self::Incomplete { completion } => {
+                write!(f, "incomplete, complete by adding {}", completion)
+            }
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Delim {
+    shape: DelimShape,
+    ty: DelimTy,
+}
+
+/// The index of a pair in [`Delims`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct DelimShape(usize);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum DelimTy {
     Open,
     Close,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+[({(<(())[]>[[{[]{<()<>>
+[(()[<>])]({[<{<<[]>>(
+{([(<{}[<>[]}>{[]{[(<()>
+(((({<>}<{<{<>}{[]{[]{}
+[[<[([]))<([[{}[[()]]]
+[{[{({}]{}}([{[{{{}}([]
+{<[[]]>}<{[{[{[]{()[[[]
+[<(<(<(<{}))><([]([]()
+<{([([[(<>()){}]>(<<{{
+<{([{{}}[<[[[<>{}]]]>[]]";
+
+    #[test]
+    fn example_statuses_are_correct() {
+        let delims = Delims::default();
+        let statuses = EXAMPLE.lines().map(|l| delims.check(l)).collect::<Vec<_>>();
+        assert_eq!(
+            LineStatus::Incomplete {
+                completion: "}}]])})]".to_string()
+            },
+            statuses[0]
+        );
+        assert_eq!(
+            LineStatus::Corrupted {
+                column: 12,
+                expected: Some(']'),
+                found: '}'
+            },
+            statuses[2]
+        );
+        assert_eq!(
+            "column 13: expected ], but found } instead",
+            statuses[2].to_string()
+        );
+
+        let scores = Scores::default();
+        let corrupted = statuses.iter().filter_map(|s| scores.corrupted(s));
+        assert_eq!(Ok(26397), corrupted.sum::<Result<u64, _>>());
+        let incomplete = statuses
+            .iter()
+            .filter_map(|s| scores.incomplete(s))
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(Ok(vec![288957, 5566, 1480781, 995444, 294]), incomplete);
+    }
+
+    #[test]
+    fn valid_and_unmatched_lines_are_reported() {
+        let delims = Delims::default();
+        assert_eq!(LineStatus::Valid, delims.check("([]){<>}"));
+        assert_eq!(
+            LineStatus::Corrupted {
+                column: 2,
+                expected: None,
+                found: ']'
+            },
+            delims.check("()]")
+        );
+        assert_eq!(None, Scores::default().incomplete(&LineStatus::Valid));
+    }
+
+    #[test]
+    fn custom_delimiters_are_supported() {
+        let delims = Delims::new(vec![('«', '»'), ('(', ')')]);
+        assert_eq!(LineStatus::Valid, delims.check("key = «a (b) c»"));
+        assert_eq!(
+            LineStatus::Corrupted {
+                column: 2,
+                expected: Some(')'),
+                found: '»'
+            },
+            delims.check("«(»")
+        );
+        assert_eq!(
+            LineStatus::Incomplete {
+                completion: ")»".to_string()
+            },
+            delims.check("«[(")
+        );
+
+        let scores = Scores::new(
+            HashMap::from([('»', 10)]),
+            HashMap::from([('»', 1), (')', 2)]),
+        );
+        assert_eq!(Some(Ok(10)), scores.corrupted(&delims.check("«(»")));
+        assert_eq!(Some(Ok(2 * 5 + 1)), scores.incomplete(&delims.check("«[(")));
+    }
+
+    #[test]
+    fn missing_scores_are_reported() {
+        let delims = Delims::new(vec![('«', '»'), ('(', ')')]);
+        let scores = Scores::new(HashMap::from([('»', 10)]), HashMap::from([('»', 1)]));
+        let err = scores.corrupted(&delims.check("«)")).unwrap().unwrap_err();
+        assert_eq!(MissingScore(')'), err);
+        assert_eq!("no score given for )", err.to_string());
+        assert_eq!(
+            Some(Err(MissingScore(')'))),
+            scores.incomplete(&delims.check("«("))
+        );
+    }
+
+    /// The fewest edits to reach `target`, by trying every combination of up to `max` edits.
+    fn brute_force_repair(delims: &Delims, line: &str, target: RepairTarget, max: usize) -> usize {
+        let alphabet = delims
+            .pairs
+            .iter()
+            .flat_map(|(open, close)| [*open, *close])
+            .collect::<Vec<_>>();
+        let reached = |line: &str| match delims.check(line) {
+            LineStatus::Valid => true,
+            LineStatus::Incomplete { .. } => target == RepairTarget::Completable,
+            LineStatus::Corrupted { .. } => false,
+        };
+
+        let mut lines = vec![line.to_string()];
+        for edits in 0..=max {
+            if lines.iter().any(|l| reached(l)) {
+                return edits;
+            }
+            let mut next = Vec::new();
+            for line in &lines {
+                let chars = line.chars().collect::<Vec<_>>();
+                for idx in 0..=chars.len() {
+                    let (before, after) = (&chars[..idx], &chars[idx..]);
+                    for c in &alphabet {
+                        next.push(before.iter().chain([c]).chain(after).collect::<String>());
+                    }
+                    if let Some((_, rest)) = after.split_first() {
+                        next.push(before.iter().chain(rest).collect::<String>());
+                        for c in &alphabet {
+                            next.push(before.iter().chain([c]).chain(rest).collect::<String>());
+                        }
+                    }
+                }
+            }
+            next.sort_unstable();
+            next.dedup();
+            lines = next;
+        }
+        panic!("{} needs more than {} edits", line, max);
+    }
+
+    #[test]
+    fn corrupted_lines_are_repaired() {
+        let delims = Delims::default();
+        let repair = delims.repair("{([(<{}[<>[]}>{[]{[(<()>", RepairTarget::Completable);
+        assert_eq!(vec![Edit::Replace { column: 12, c: ']' }], repair.edits);
+        assert_eq!("{([(<{}[<>[]]>{[]{[(<()>", repair.line);
+
+        let repair = delims.repair("(]", RepairTarget::Balanced);
+        assert_eq!(1, repair.edits.len());
+        assert_eq!(LineStatus::Valid, delims.check(&repair.line));
+
+        let repair = delims.repair("a)b(", RepairTarget::Balanced);
+        assert_eq!(2, repair.edits.len());
+        assert_eq!(LineStatus::Valid, delims.check(&repair.line));
+    }
+
+    #[test]
+    fn repairs_are_minimal() {
+        let delims = Delims::default();
+        let lines = ["(]", "([)]", "<{]>)", "[[(>", "))((", "{(})", "([{<"];
+        for line in lines {
+            for target in [RepairTarget::Completable, RepairTarget::Balanced] {
+                let repair = delims.repair(line, target);
+                let status = delims.check(&repair.line);
+                match target {
+                    RepairTarget::Completable => {
+                        assert!(!matches!(status, LineStatus::Corrupted { .. }))
+                    }
+                    RepairTarget::Balanced => assert_eq!(LineStatus::Valid, status),
+                }
+                let expected = brute_force_repair(&delims, line, target, 3);
+                assert_eq!(expected, repair.edits.len(), "{} {:?}", line, target);
+            }
+        }
+    }
+
+    #[test]
+    fn example_corrupted_lines_need_exact_edits() {
+        let delims = Delims::default();
+        let corrupted = EXAMPLE
+            .lines()
+            .filter(|line| matches!(delims.check(line), LineStatus::Corrupted { .. }));
+        let expected = [(1, 5), (1, 4), (1, 5), (1, 5), (1, 6)];
+        for (line, (completable, balanced)) in corrupted.zip(expected) {
+            let repair = delims.repair(line, RepairTarget::Completable);
+            assert_eq!(completable, repair.edits.len(), "{}", line);
+            assert!(!matches!(
+                delims.check(&repair.line),
+                LineStatus::Corrupted { .. }
+            ));
+
+            let repair = delims.repair(line, RepairTarget::Balanced);
+            assert_eq!(balanced, repair.edits.len(), "{}", line);
+            assert_eq!(LineStatus::Valid, delims.check(&repair.line));
+        }
+    }
+
+    #[test]
+    fn repair_targets_are_parsed() {
+        assert_eq!(Ok(RepairTarget::Balanced), "balanced".parse());
+        assert_eq!(Ok(RepairTarget::Completable), "completable".parse());
+        let err = "closed".parse::<RepairTarget>().unwrap_err();
+        assert_eq!(
+            "`closed` isn't a repair target, expected `completable` or `balanced`",
+            err.to_string()
+        );
+    }
+}
@@ -1,74 +1,127 @@
-use super::utils::sep_array_10;
-use itertools::iproduct;
-use nom::IResult;
+use super::utils::{Point, Simulation, Vec2d};
+use std::{error::Error, fmt};
 
 pub fn star_1(data: String) {
-    let mut octos = parse(&data);
+    let mut octos = Octopuses::new(parse(&data), 9);
     let mut total_flashes = 0;
 
     for _ in 0..100 {
-        let (new_octos, num_flashes) = step(octos);
-        octos = new_octos;
-        total_flashes += num_flashes;
+        octos.step();
+        total_flashes += octos.flashed.len();
     }
 
     println!("{}", total_flashes);
 }
 
 pub fn star_2(data: String) {
-    let mut octos = parse(&data);
+    let mut octos = Octopuses::new(parse(&data), 9);
+    let steps = octos.run_until(|o| o.flashed.len() == o.energy.len());
+    println!("{}", steps);
+}
+
+/// Prints the energy of each octopus after the number of steps given after the input file, or
+/// after 100 steps by default.
+pub fn energy_after(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let steps = match args {
+        [] => 100,
+        [arg, ..] => arg.parse().map_err(|_| InvalidSteps(arg.clone()))?,
+    };
+    let mut octos = Octopuses::new(parse(&data), 9);
+    octos.run_with_cycles(steps);
+    print!("{}", render(&octos.energy));
+    Ok(())
+}
 
-    for i in 0.. {
-        let (new_octos, num_flashes) = step(octos);
-        octos = new_octos;
+/// Prints the step the octopuses start repeating from, and how often they repeat.
+pub fn cycle(data: String) -> Result<(), Box<dyn Error>> {
+    const LIMIT: usize = 1_000_000;
 
-        if num_flashes == 100 {
-            println!("{}", i);
-            break;
-        }
+    let octos = Octopuses::new(parse(&data), 9);
+    let cycle = octos.find_cycle(LIMIT).ok_or(NoCycle(LIMIT))?;
+    println!("start: {}, period: {}", cycle.start, cycle.period);
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InvalidSteps(String);
+
+impl fmt::Display for InvalidSteps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` isn't a number of steps", self.0)
     }
 }
 
-fn step(mut octos: [[u8; 10]; 10]) -> ([[u8; 10]; 10], usize) {
-    let mut num_flashes = 0;
-    let mut is_flashing = false;
-    for (row, col) in iproduct!(0..10, 0..10) {
-        octos[row][col] += 1;
-        if octos[row][col] > 9 {
-            num_flashes += 1;
-            is_flashing = true;
-        }
+impl Error for InvalidSteps {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NoCycle(usize);
+
+impl fmt::Display for NoCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the octopuses don't repeat within {} steps", self.0)
     }
+}
 
-    while is_flashing {
-        is_flashing = false;
-        let mut new_octos = octos;
+impl Error for NoCycle {}
 
-        for (row, col) in iproduct!(0..10, 0..10) {
-            let octo = &mut new_octos[row][col];
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Octopuses {
+    energy: Vec2d<u8>,
+    /// Octopuses flash once their energy goes above this.
+    threshold: u8,
+    /// The octopuses that flashed during the last step, in the order they flashed.
+    flashed: Vec<(usize, usize)>,
+}
 
-            if octos[row][col] > 9 {
-                *octo = 0;
-            }
+impl Octopuses {
+    fn new(energy: Vec2d<u8>, threshold: u8) -> Self {
+        Self {
+            energy,
+            threshold,
+            flashed: Vec::new(),
+        }
+    }
+}
 
-            if *octo == 0 {
-                continue;
-            }
+impl Simulation for Octopuses {
+    fn step(&mut self) {
+        self.flashed = step(&mut self.energy, self.threshold);
+    }
+}
 
-            let flash_adjust = neighbors(row, col, 10, 10)
-                .filter(|(row, col)| octos[*row][*col] > 9)
-                .count();
-            *octo += flash_adjust as u8;
-            if *octo > 9 {
-                num_flashes += 1;
-                is_flashing = true;
+/// Runs a single step, returning the octopuses that flashed.
+fn step(octos: &mut Vec2d<u8>, threshold: u8) -> Vec<(usize, usize)> {
+    let (width, height) = (octos.width(), octos.height());
+    let mut flashed = Vec::new();
+    for coord in octos.all_coords() {
+        let octo = &mut octos[coord];
+        *octo = octo.saturating_add(1);
+        if *octo > threshold {
+            flashed.push(coord);
+        }
+    }
+
+    // Every octopus is pushed exactly once, when it first goes over the threshold, so this is
+    // also the queue of flashes whose neighbors haven't been energized yet.
+    let mut next = 0;
+    while let Some(&(col, row)) = flashed.get(next) {
+        next += 1;
+        for (row, col) in neighbors(row, col, width, height) {
+            let octo = &mut octos[(col, row)];
+            if *octo <= threshold {
+                *octo += 1;
+                if *octo > threshold {
+                    flashed.push((col, row));
+                }
             }
         }
+    }
 
-        octos = new_octos;
+    for coord in &flashed {
+        octos[*coord] = 0;
     }
 
-    (octos, num_flashes)
+    flashed
 }
 
 fn neighbors(
@@ -77,42 +130,171 @@ fn neighbors(
     width: usize,
     height: usize,
 ) -> impl Iterator<Item = (usize, usize)> {
-    let rows_start = if row == 0 { row } else { row - 1 };
-    let rows_end = if row >= height - 1 { row } else { row + 1 };
-    let cols_start = if col == 0 { col } else { col - 1 };
-    let cols_end = if col >= width - 1 { col } else { col + 1 };
-    let rows = rows_start..=rows_end;
-    let cols = cols_start..=cols_end;
-    iproduct!(cols, rows)
-        .map(|(c, r)| (r, c))
-        .filter(move |(r, c)| *r != row || *c != col)
+    let (last_row, last_col) = (row.max(height - 1), col.max(width - 1));
+    Point::<_, 2>::new(row, col)
+        .all_neighbors()
+        .filter(move |p| p.x() <= last_row && p.y() <= last_col)
+        .map(|p| (p.x(), p.y()))
 }
 
-pub fn parse(input: &str) -> [[u8; 10]; 10] {
-    super::utils::parse(octopodes, input)
+/// Draws the energy levels as rows of digits.
+fn render(energy: &Vec2d<u8>) -> String {
+    let mut out = String::new();
+    for (coord, &level) in energy.all_coords().zip(energy.iter()) {
+        out.push(char::from_digit(u32::from(level), 10).unwrap_or('+'));
+        if coord.0 + 1 == energy.width() {
+            out.push('\n');
+        }
+    }
+    out
 }
 
-pub fn octopodes(input: &str) -> IResult<&str, [[u8; 10]; 10]> {
-    use nom::character::complete::line_ending;
-
-    sep_array_10(line_ending, row)(input)
+fn parse(input: &str) -> Vec2d<u8> {
+    super::utils::parse(super::utils::digit_grid, input)
 }
 
-pub fn row(input: &str) -> IResult<&str, [u8; 10]> {
-    use nom::combinator::success;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Rng;
+
+    const EXAMPLE: &str = "5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526";
+
+    #[test]
+    fn example_flashes_are_counted() {
+        let mut octos = Octopuses::new(parse(EXAMPLE), 9);
+        let mut total_flashes = 0;
+        for _ in 0..100 {
+            octos.step();
+            total_flashes += octos.flashed.len();
+        }
+        assert_eq!(1656, total_flashes);
+    }
 
-    sep_array_10(success(()), digit)(input)
-}
+    #[test]
+    fn first_full_flash_is_found() {
+        let mut octos = Octopuses::new(parse(EXAMPLE), 9);
+        assert_eq!(195, octos.run_until(|o| o.flashed.len() == 100));
+    }
 
-pub fn digit(input: &str) -> IResult<&str, u8> {
-    use nom::{character::complete::anychar, combinator::map_opt};
+    #[test]
+    fn example_repeats_once_synchronized() {
+        let octos = Octopuses::new(parse(EXAMPLE), 9);
+        let cycle = octos.find_cycle(1000).unwrap();
+        assert_eq!(10, cycle.period);
+        let mut start = octos.clone();
+        start.run(cycle.start);
+        let mut end = start.clone();
+        end.run(cycle.period);
+        assert_eq!(start, end);
+
+        let mut skipped = octos.clone();
+        skipped.run_with_cycles(1234);
+        let mut stepped = octos;
+        stepped.run(1234);
+        assert_eq!(stepped, skipped);
+        assert_eq!(EXAMPLE.to_string() + "\n", render(&parse(EXAMPLE)));
+    }
 
-    map_opt(anychar, |c| c.to_digit(10).map(|d| d as u8))(input)
-}
+    /// The original implementation, which rescans the whole grid for each wave of flashes.
+    fn rescanning_step(octos: &mut Vec2d<u8>, threshold: u8) -> Vec<(usize, usize)> {
+        let (width, height) = (octos.width(), octos.height());
+        let mut flashed = Vec::new();
+        let mut is_flashing = false;
+        for coord in octos.all_coords() {
+            let octo = &mut octos[coord];
+            *octo = octo.saturating_add(1);
+            if *octo > threshold {
+                flashed.push(coord);
+                is_flashing = true;
+            }
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        while is_flashing {
+            is_flashing = false;
+            let mut new_octos = octos.clone();
+
+            for coord in octos.all_coords() {
+                let octo = &mut new_octos[coord];
+
+                if octos[coord] > threshold {
+                    *octo = 0;
+                }
+
+                if *octo == 0 {
+                    continue;
+                }
+
+                let (col, row) = coord;
+                let flash_adjust = neighbors(row, col, width, height)
+                    .filter(|(row, col)| octos[(*col, *row)] > threshold)
+                    .count();
+                *octo = octo.saturating_add(flash_adjust as u8);
+                if *octo > threshold {
+                    flashed.push(coord);
+                    is_flashing = true;
+                }
+            }
+
+            *octos = new_octos;
+        }
+
+        flashed
+    }
+
+    #[test]
+    fn matches_rescanning_step() {
+        let mut rng = Rng::new(0x2545_f491);
+        let mut generated = Vec2d::repeat(0, 60, 40);
+        for coord in generated.all_coords() {
+            generated[coord] = rng.below(10) as u8;
+        }
+
+        for threshold in [9, 5] {
+            let mut octos = generated.clone();
+            let mut expected = octos.clone();
+            for _ in 0..30 {
+                let mut flashed = step(&mut octos, threshold);
+                let mut expected_flashed = rescanning_step(&mut expected, threshold);
+                flashed.sort_unstable();
+                expected_flashed.sort_unstable();
+                assert_eq!(expected, octos);
+                assert_eq!(expected_flashed, flashed);
+            }
+        }
+    }
+
+    #[test]
+    fn small_grid_cascades() {
+        let mut octos = parse("11111\n19991\n19191\n19991\n11111");
+
+        let flashed = step(&mut octos, 9);
+        assert_eq!(parse("34543\n40004\n50005\n40004\n34543"), octos);
+        assert_eq!(9, flashed.len());
+        assert!(flashed.iter().all(|c| octos[*c] == 0));
+
+        let flashed = step(&mut octos, 9);
+        assert_eq!(parse("45654\n51115\n61116\n51115\n45654"), octos);
+        assert!(flashed.is_empty());
+    }
+
+    #[test]
+    fn threshold_is_configurable() {
+        let mut octos = parse("012\n120");
+        let flashed = step(&mut octos, 2);
+        assert_eq!(vec![(2, 0), (1, 1)], flashed[..2].to_vec());
+        assert_eq!(parse("000\n000"), octos);
+        assert_eq!(6, flashed.len());
+    }
 
     mod neighbors {
         use super::neighbors;
@@ -1,115 +1,100 @@
+use super::graph::{Graph, VisitPolicy};
+use itertools::Itertools;
 use nom::IResult;
-use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    fmt, ops,
-};
+use std::{error::Error, fmt};
 
 pub fn star_1(data: String) {
     let graph = parse(&data);
-    let count = graph.num_paths(false);
+    let count = graph.count_paths(Node::Start, Node::End, CaveRules::new(false));
     println!("{}", count);
 }
 
 pub fn star_2(data: String) {
     let graph = parse(&data);
-    let count = graph.num_paths(true);
+    let count = graph.count_paths(Node::Start, Node::End, CaveRules::new(true));
     println!("{}", count);
 }
 
-fn parse(input: &str) -> Graph {
-    super::utils::parse(graph, input)
+/// Prints every path through the caves in sorted order. Giving `twice` after the input file lets
+/// a single small cave be visited twice, as in star 2.
+pub fn list_paths(data: String, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut can_visit_twice = false;
+    for arg in args {
+        match arg.as_str() {
+            "twice" => can_visit_twice = true,
+            _ => return Err(UnknownRule(arg.clone()).into()),
+        }
+    }
+    for path in paths(&parse(&data), can_visit_twice) {
+        println!("{}", path);
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Graph<'a> {
-    edges: HashMap<Node<'a>, HashSet<Node<'a>>>,
-}
-
-impl<'a> Graph<'a> {
-    fn new() -> Self {
-        Self::default()
-    }
+struct UnknownRule(String);
 
-    fn add_edge(&mut self, start: Node<'a>, end: Node<'a>) {
-        self.edges
-            .entry(start)
-            .or_insert_with(HashSet::new)
-            .insert(end);
-        self.edges
-            .entry(end)
-            .or_insert_with(HashSet::new)
-            .insert(start);
+impl fmt::Display for UnknownRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` isn't a cave rule, expected `twice`", self.0)
     }
+}
 
-    fn num_paths(&self, can_visit_twice: bool) -> usize {
-        let mut visited = HashSet::new();
-        self.num_paths_from(Node::Start, &mut visited, can_visit_twice, None)
-    }
+impl Error for UnknownRule {}
+
+/// Every path from the start to the end, written as comma separated caves, in sorted order.
+fn paths(graph: &Graph<Node>, can_visit_twice: bool) -> Vec<String> {
+    let mut paths = graph
+        .paths(Node::Start, Node::End, CaveRules::new(can_visit_twice))
+        .iter()
+        .map(|path| path.iter().join(","))
+        .collect::<Vec<_>>();
+    paths.sort_unstable();
+    paths
+}
 
-    fn num_paths_from(
-        &self,
-        node: Node<'a>,
-        visited: &mut HashSet<Node<'a>>,
-        can_visit_twice: bool,
-        visited_twice: Option<Node<'a>>,
-    ) -> usize {
-        let mut num_paths = 0;
-
-        for next in &self[node] {
-            match next {
-                Node::Start => {}
-                Node::End => {
-                    num_paths += 1;
-                }
-                Node::Big(_) => {
-                    num_paths +=
-                        self.num_paths_from(*next, visited, can_visit_twice, visited_twice);
-                }
-                Node::Small(_) => {
-                    let visited_twice = if visited.contains(next) {
-                        if !can_visit_twice || visited_twice.is_some() {
-                            continue;
-                        }
-                        Some(*next)
-                    } else {
-                        visited_twice
-                    };
-
-                    visited.insert(*next);
-                    num_paths +=
-                        self.num_paths_from(*next, visited, can_visit_twice, visited_twice);
-
-                    match visited_twice {
-                        Some(n) if n == *next => {}
-                        _ => {
-                            visited.remove(next);
-                        }
-                    }
-                }
-            }
-        }
+fn parse(input: &str) -> Graph<Node> {
+    super::utils::parse(graph, input)
+}
 
-        num_paths
-    }
+/// Big caves can be visited any number of times, and small caves only once, except that a single
+/// small cave may be visited twice if `can_visit_twice` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CaveRules<'a> {
+    can_visit_twice: bool,
+    visited_twice: Option<Node<'a>>,
 }
 
-impl<'a> Default for Graph<'a> {
-    fn default() -> Self {
+impl<'a> CaveRules<'a> {
+    fn new(can_visit_twice: bool) -> Self {
         Self {
-            edges: HashMap::default(),
+            can_visit_twice,
+            visited_twice: None,
         }
     }
 }
 
-impl<'a> ops::Index<Node<'a>> for Graph<'a> {
-    type Output = HashSet<Node<'a>>;
+impl<'a> VisitPolicy<Node<'a>> for CaveRules<'a> {
+    fn enter(&mut self, node: Node<'a>, visits: usize) -> bool {
+        match node {
+            Node::Big(_) => true,
+            _ if visits == 0 => true,
+            Node::Small(_) if self.can_visit_twice && self.visited_twice.is_none() => {
+                self.visited_twice = Some(node);
+                true
+            }
+            _ => false,
+        }
+    }
 
-    fn index(&self, node: Node<'a>) -> &Self::Output {
-        self.edges.index(&node)
+    fn leave(&mut self, node: Node<'a>, visits: usize) {
+        if visits == 1 && self.visited_twice == Some(node) {
+            self.visited_twice = None;
+        }
     }
 }
 
-fn graph(input: &str) -> IResult<&str, Graph> {
+fn graph(input: &str) -> IResult<&str, Graph<Node>> {
     use nom::{
         character::complete::line_ending, combinator::opt, multi::fold_many0, sequence::terminated,
     };
@@ -118,7 +103,7 @@ fn graph(input: &str) -> IResult<&str, Graph> {
         terminated(edge, opt(line_ending)),
         Graph::new,
         |mut map, (start, end)| {
-            map.add_edge(start, end);
+            map.add_undirected_edge(start, end);
             map
         },
     )(input)
@@ -195,4 +180,33 @@ mod tests {
         let name = "star";
         assert_eq!(Node::Small("star"), Node::from(name));
     }
+
+    #[test]
+    fn path_counts_are_correct() {
+        let graph = parse("start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end");
+        let count = |can_visit_twice| {
+            graph.count_paths(Node::Start, Node::End, CaveRules::new(can_visit_twice))
+        };
+        assert_eq!(10, count(false));
+        assert_eq!(36, count(true));
+    }
+
+    #[test]
+    fn paths_are_listed() {
+        let graph = parse("start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end");
+        let expected = [
+            "start,A,b,A,c,A,end",
+            "start,A,b,A,end",
+            "start,A,b,end",
+            "start,A,c,A,b,A,end",
+            "start,A,c,A,b,end",
+            "start,A,c,A,end",
+            "start,A,end",
+            "start,b,A,c,A,end",
+            "start,b,A,end",
+            "start,b,end",
+        ];
+        assert_eq!(&expected[..], &paths(&graph, false)[..]);
+        assert_eq!(36, paths(&graph, true).len());
+    }
 }
@@ -1,4 +1,4 @@
-use super::utils::Vec2d;
+use super::utils::{BoundingBox, Point, Vec2d};
 use nom::{
     character::complete::{char as char_, u64 as u64_},
     combinator::map,
@@ -7,13 +7,15 @@ use nom::{
 };
 use std::collections::HashSet;
 
+type Coord = Point<u64, 2>;
+
 pub fn star_1(data: String) {
     let values = parse(&data);
     let mut folded = HashSet::new();
 
     let fold = values.folds[0];
     for coord in values.coords {
-        folded.insert(coord.folded(fold));
+        folded.insert(fold.apply(coord));
     }
 
     println!("{}", folded.len());
@@ -25,7 +27,7 @@ pub fn star_2(data: String) {
 
     for &(mut coord) in &values.coords {
         for &fold in &values.folds {
-            coord = coord.folded(fold);
+            coord = fold.apply(coord);
         }
         folded.insert(coord);
     }
@@ -34,17 +36,17 @@ pub fn star_2(data: String) {
 }
 
 fn print_coords(coords: &HashSet<Coord>) {
-    let mut max_x = 0;
-    let mut max_y = 0;
-    for coord in coords {
-        max_x = max_x.max(coord.x);
-        max_y = max_y.max(coord.y);
-    }
-    let mut image = Vec2d::repeat(' ', (max_x + 1) as usize, (max_y + 1) as usize);
+    let max = match BoundingBox::around(coords.iter().copied()) {
+        Some(bounds) => bounds.max,
+        None => return,
+    };
+    let max_x = max.x();
+    let mut image = Vec2d::repeat(false, (max_x + 1) as usize, (max.y() + 1) as usize);
 
     for coord in coords {
-        image[(coord.x as usize, coord.y as usize)] = '#';
+        image[(coord.x() as usize, coord.y() as usize)] = true;
     }
+    let image = image.map(|is_dot| if is_dot { '#' } else { ' ' });
 
     let line_ends = image.all_coords().map(|(x, _)| x == max_x as usize);
 
@@ -79,41 +81,6 @@ fn values(input: &str) -> IResult<&str, Values> {
     )(input)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Coord {
-    x: u64,
-    y: u64,
-}
-
-impl Coord {
-    fn folded(self, fold: Fold) -> Self {
-        fn folded(val: u64, fold: u64) -> u64 {
-            if val <= fold {
-                val
-            } else {
-                2 * fold - val
-            }
-        }
-
-        match fold.axis {
-            Axis::X => Self {
-                x: folded(self.x, fold.pos),
-                y: self.y,
-            },
-            Axis::Y => Self {
-                x: self.x,
-                y: folded(self.y, fold.pos),
-            },
-        }
-    }
-}
-
-impl From<(u64, u64)> for Coord {
-    fn from((x, y): (u64, u64)) -> Self {
-        Self { x, y }
-    }
-}
-
 fn coord(input: &str) -> IResult<&str, Coord> {
     map(separated_pair(u64_, char_(','), u64_), Coord::from)(input)
 }
@@ -124,6 +91,19 @@ struct Fold {
     pos: u64,
 }
 
+impl Fold {
+    fn apply(self, mut coord: Coord) -> Coord {
+        let axis = match self.axis {
+            Axis::X => 0,
+            Axis::Y => 1,
+        };
+        if coord[axis] > self.pos {
+            coord[axis] = 2 * self.pos - coord[axis];
+        }
+        coord
+    }
+}
+
 fn fold(input: &str) -> IResult<&str, Fold> {
     use nom::{bytes::complete::tag, sequence::preceded};
 
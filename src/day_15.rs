@@ -1,5 +1,4 @@
-use super::utils::{Vec2d, Vec2dBuilder};
-use nom::{combinator::map, IResult};
+use super::utils::Vec2d;
 use std::cmp::Ordering;
 
 pub fn star_1(data: String) {
@@ -92,44 +91,6 @@ impl PartialOrd for State {
     }
 }
 
-// TODO: Dedupe this & day_09.
-
 fn parse(input: &str) -> Vec2d<u8> {
-    super::utils::parse(vec2d_u8, input)
-}
-
-fn vec2d_u8(input: &str) -> IResult<&str, Vec2d<u8>> {
-    use nom::multi::fold_many0;
-
-    map(
-        fold_many0(digit_or_newline, Vec2dBuilder::new, |mut b, d| {
-            match d {
-                DigitOrNewline::Digit(x) => b.push(x),
-                DigitOrNewline::Newline => b.finish_row(),
-            }
-            b
-        }),
-        Vec2dBuilder::build,
-    )(input)
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum DigitOrNewline {
-    Digit(u8),
-    Newline,
-}
-
-fn digit_or_newline(input: &str) -> IResult<&str, DigitOrNewline> {
-    use nom::{branch::alt, character::complete::line_ending, combinator::value};
-
-    alt((
-        map(digit, DigitOrNewline::Digit),
-        value(DigitOrNewline::Newline, line_ending),
-    ))(input)
-}
-
-fn digit(input: &str) -> IResult<&str, u8> {
-    use nom::{character::complete::anychar, combinator::map_opt};
-
-    map_opt(anychar, |c| c.to_digit(10).map(|x| x as u8))(input)
+    super::utils::parse(super::utils::digit_grid, input)
 }
@@ -1,151 +1,199 @@
-use nom::{
-    combinator::map,
-    error::{Error, ErrorKind, ParseError},
-    Err as NomErr, IResult, InputIter, InputLength, Slice,
+use ::num::{
+    traits::{CheckedAdd, CheckedMul},
+    BigUint, One, ToPrimitive, Zero,
+};
+use std::{
+    fmt,
+    io::{self, BufReader, Read},
 };
-use std::ops::{AddAssign, RangeFrom, RangeTo, Shl, Shr};
 
 pub fn star_1(data: String) {
-    let packet = parse(&data);
-    let sum = packet.version_sum();
-    println!("{}", sum);
+    match version_sum_events(Decoder::new(data.as_bytes())) {
+        Ok(sum) => println!("{}", sum),
+        Err(err) => println!("couldn't read transmission: {}", err),
+    }
 }
 
 pub fn star_2(data: String) {
-    let packet = parse(&data);
-    let eval = packet.eval();
-    println!("{}", eval);
-}
-
-fn parse(data: &str) -> Packet {
-    use super::utils::parse;
-
-    let hex = parse(hex_string, data);
-    parse(packet, BitSlice::from(&hex[..]))
+    let decoder = || Decoder::new(data.as_bytes());
+    match eval_events::<usize, _>(decoder()) {
+        Ok(eval) => println!("{}", eval),
+        Err(StreamError::Eval(EvalError::Overflow(err))) => {
+            match eval_events::<BigUint, _>(decoder()) {
+                Ok(eval) => println!("{}, exact value is {}", err, eval),
+                Err(err) => println!("{}", err),
+            }
+        }
+        Err(err) => println!("{}", err),
+    }
 }
 
-fn hex_string(mut input: &str) -> IResult<&str, Vec<u8>> {
-    use nom::multi::many1;
-
-    many1(hex_u8)(input)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OperatorType {
+    Sum,
+    Product,
+    Minimum,
+    Maximum,
+    GreaterThan,
+    LessThan,
+    EqualTo,
 }
 
-fn hex_u8(input: &str) -> IResult<&str, u8> {
-    use nom::{bytes::complete::take_while_m_n, combinator::map_res};
-    map_res(take_while_m_n(2, 2, |c: char| c.is_digit(16)), |i| {
-        u8::from_str_radix(i, 16)
-    })(input)
+impl OperatorType {
+    fn is_comparison(self) -> bool {
+        matches!(self, Self::GreaterThan | Self::LessThan | Self::EqualTo)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Packet {
-    version: u8,
-    data: PacketData,
+/// The running value of an operator packet whose subpackets are still being evaluated.
+struct Accumulator<T> {
+    op_type: OperatorType,
+    acc: Option<T>,
+    /// The number of subpackets pushed so far.
+    count: usize,
 }
 
-impl Packet {
-    fn literal(version: u8, num: usize) -> Self {
+impl<T: Value> Accumulator<T> {
+    fn new(op_type: OperatorType) -> Self {
         Self {
-            version,
-            data: PacketData::Literal(num),
+            op_type,
+            acc: None,
+            count: 0,
         }
     }
 
-    fn operator(version: u8, op_type: OperatorType, subpackets: Vec<Packet>) -> Self {
-        Self {
-            version,
-            data: PacketData::Operator {
-                op_type,
-                subpackets,
-            },
+    fn push(&mut self, value: T) -> Result<(), Overflow> {
+        use OperatorType::*;
+
+        self.count += 1;
+        if self.op_type.is_comparison() && self.count > 2 {
+            // Reported by `finish`, once the full count is known.
+            return Ok(());
         }
+
+        self.acc = Some(match (self.op_type, self.acc.take()) {
+            (_, None) => value,
+            (Sum, Some(acc)) => acc.checked_add(&value).ok_or(Overflow)?,
+            (Product, Some(acc)) => acc.checked_mul(&value).ok_or(Overflow)?,
+            (Minimum, Some(acc)) => acc.min(value),
+            (Maximum, Some(acc)) => acc.max(value),
+            (GreaterThan, Some(acc)) => T::from_bool(acc > value),
+            (LessThan, Some(acc)) => T::from_bool(acc < value),
+            (EqualTo, Some(acc)) => T::from_bool(acc == value),
+        });
+        Ok(())
     }
 
-    fn version_sum(&self) -> usize {
-        let child_sum = match &self.data {
-            PacketData::Literal(_) => 0,
-            PacketData::Operator {
-                op_type,
-                subpackets,
-            } => subpackets.iter().map(Packet::version_sum).sum(),
+    fn finish(self) -> Result<T, Arity> {
+        use OperatorType::*;
+
+        let arity = Arity {
+            op_type: self.op_type,
+            subpackets: self.count,
         };
-        child_sum + (self.version as usize)
+        match (self.op_type, self.acc) {
+            (GreaterThan | LessThan | EqualTo, _) if self.count != 2 => Err(arity),
+            (_, Some(acc)) => Ok(acc),
+            (Sum, None) => Ok(T::zero()),
+            (Product, None) => Ok(T::one()),
+            (Minimum | Maximum | GreaterThan | LessThan | EqualTo, None) => Err(arity),
+        }
     }
+}
+
+/// A numeric type that packets can be evaluated in.
+trait Value: Sized + Ord + Zero + One + CheckedAdd + CheckedMul {
+    /// Converts a literal, returning `None` if it doesn't fit.
+    fn from_literal(n: &BigUint) -> Option<Self>;
 
-    fn eval(&self) -> usize {
-        self.data.eval()
+    fn from_bool(b: bool) -> Self {
+        if b {
+            Self::one()
+        } else {
+            Self::zero()
+        }
     }
 }
 
-fn packet(input: BitSlice<&[u8]>) -> IResult<BitSlice<&[u8]>, Packet> {
-    use nom::sequence::pair;
+impl Value for usize {
+    fn from_literal(n: &BigUint) -> Option<Self> {
+        n.to_usize()
+    }
+}
 
-    map(pair(num(3), packet_data), |(version, data)| Packet {
-        version,
-        data,
-    })(input)
+impl Value for BigUint {
+    fn from_literal(n: &BigUint) -> Option<Self> {
+        Some(n.clone())
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum PacketData {
-    Literal(usize),
-    Operator {
-        op_type: OperatorType,
-        subpackets: Vec<Packet>,
-    },
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "evaluation overflowed")
+    }
 }
 
-impl PacketData {
-    fn eval(&self) -> usize {
-        match self {
-            Self::Literal(n) => *n,
-            Self::Operator {
-                op_type,
-                subpackets,
-            } => op_type.eval(subpackets),
-        }
+impl std::error::Error for Overflow {}
+
+/// An operator packet with the wrong number of subpackets for its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Arity {
+    op_type: OperatorType,
+    subpackets: usize,
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expected = if self.op_type.is_comparison() {
+            "exactly 2"
+        } else {
+            "at least 1"
+        };
+        write!(
+            f,
+            "{:?} packet needs {} subpackets but has {}",
+            self.op_type, expected, self.subpackets
+        )
     }
 }
 
+impl std::error::Error for Arity {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum OperatorType {
-    Sum,
-    Product,
-    Minimum,
-    Maximum,
-    GreaterThan,
-    LessThan,
-    EqualTo,
+enum EvalError {
+    Overflow(Overflow),
+    Arity(Arity),
 }
 
-impl OperatorType {
-    fn eval(self, subpackets: &[Packet]) -> usize {
+impl From<Overflow> for EvalError {
+    fn from(err: Overflow) -> Self {
+        Self::Overflow(err)
+    }
+}
+
+impl From<Arity> for EvalError {
+    fn from(err: Arity) -> Self {
+        Self::Arity(err)
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Sum => subpackets.iter().map(Packet::eval).sum(),
-            Self::Product => subpackets.iter().map(Packet::eval).product(),
-            Self::Minimum => subpackets.iter().map(Packet::eval).min().unwrap(),
-            Self::Maximum => subpackets.iter().map(Packet::eval).max().unwrap(),
-            Self::GreaterThan => {
-                if subpackets[0].eval() > subpackets[1].eval() {
-                    1
-                } else {
-                    0
-                }
-            }
-            Self::LessThan => {
-                if subpackets[0].eval() < subpackets[1].eval() {
-                    1
-                } else {
-                    0
-                }
-            }
-            Self::EqualTo => {
-                if subpackets[0].eval() == subpackets[1].eval() {
-                    1
-                } else {
-                    0
-                }
-            }
+            Self::Overflow(err) => err.fmt(f),
+            Self::Arity(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Overflow(err) => Some(err),
+            Self::Arity(err) => Some(err),
         }
     }
 }
@@ -165,312 +213,431 @@ impl From<u8> for OperatorType {
     }
 }
 
-fn packet_data(input: BitSlice<&[u8]>) -> IResult<BitSlice<&[u8]>, PacketData> {
-    println!("packet data input: {:?}", input);
-    let (input, type_id): (_, u8) = num(3)(input)?;
-    if type_id == 4 {
-        map(literal, PacketData::Literal)(input)
-    } else {
-        let op_type = OperatorType::from(type_id);
-        let (input, subpackets) = subpackets(input)?;
-        Ok((
-            input,
-            PacketData::Operator {
-                op_type,
-                subpackets,
-            },
-        ))
-    }
+/// An event produced while decoding a transmission incrementally.
+///
+/// Operator packets are reported as a `Start`, followed by the events for each subpacket, followed
+/// by an `End`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Event {
+    Start { version: u8, op_type: OperatorType },
+    Literal { version: u8, value: BigUint },
+    End,
 }
 
-fn literal(mut input: BitSlice<&[u8]>) -> IResult<BitSlice<&[u8]>, usize> {
-    let mut acc = 0;
-    loop {
-        let (inp, bit): (_, u8) = num(1)(input)?;
-        let (inp, data): (_, usize) = num(4)(inp)?;
-        input = inp;
-        acc = (acc << 4) + data;
-        if bit == 0 {
-            break;
-        }
-    }
-    Ok((input, acc))
+/// Decodes a hex transmission from a reader into a stream of [`Event`]s.
+///
+/// Only the chain of currently open operator packets is kept in memory, so transmissions of any
+/// length can be processed without building the packet tree.
+struct Decoder<R> {
+    bits: BitReader<R>,
+    open: Vec<Frame>,
+    started: bool,
 }
 
-fn subpackets(input: BitSlice<&[u8]>) -> IResult<BitSlice<&[u8]>, Vec<Packet>> {
-    let (input, length_type_bit): (_, u8) = num(1)(input)?;
-    if length_type_bit == 0 {
-        length_type_0_subpackets(input)
-    } else {
-        length_type_1_subpackets(input)
-    }
+/// The bounds of an open operator packet's subpackets.
+enum Frame {
+    /// The subpackets end once this many bits have been read.
+    Bits { end: usize },
+    /// This many subpackets have yet to be started.
+    Count { remaining: usize },
 }
 
-fn length_type_0_subpackets(input: BitSlice<&[u8]>) -> IResult<BitSlice<&[u8]>, Vec<Packet>> {
-    use nom::multi::many1;
+impl<R: Read> Decoder<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            bits: BitReader::new(reader),
+            open: Vec::new(),
+            started: false,
+        }
+    }
+
+    fn packet(&mut self) -> io::Result<Event> {
+        let version = self.bits.read(3)? as u8;
+        let type_id = self.bits.read(3)? as u8;
+
+        if type_id == 4 {
+            let mut value = BigUint::zero();
+            loop {
+                let bit = self.bits.read(1)?;
+                value = (value << 4u8) + self.bits.read(4)?;
+                if bit == 0 {
+                    break;
+                }
+            }
+            return Ok(Event::Literal { version, value });
+        }
 
-    let (input, len) = num(15)(input)?;
-    let (input, subslice) = slice(len)(input)?;
-    let (_, packets) = many1(packet)(subslice)?;
-    Ok((input, packets))
+        let frame = if self.bits.read(1)? == 0 {
+            let len = self.bits.read(15)?;
+            Frame::Bits {
+                end: self.bits.position + len,
+            }
+        } else {
+            Frame::Count {
+                remaining: self.bits.read(11)?,
+            }
+        };
+        self.open.push(frame);
+
+        Ok(Event::Start {
+            version,
+            op_type: OperatorType::from(type_id),
+        })
+    }
 }
 
-fn length_type_1_subpackets(input: BitSlice<&[u8]>) -> IResult<BitSlice<&[u8]>, Vec<Packet>> {
-    use nom::multi::length_count;
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.open.last_mut() {
+            None if self.started => return None,
+            None => self.started = true,
+            Some(Frame::Bits { end }) if self.bits.position >= *end => {
+                self.open.pop();
+                return Some(Ok(Event::End));
+            }
+            Some(Frame::Count { remaining: 0 }) => {
+                self.open.pop();
+                return Some(Ok(Event::End));
+            }
+            Some(Frame::Count { remaining }) => *remaining -= 1,
+            Some(Frame::Bits { .. }) => {}
+        }
 
-    length_count::<_, _, usize, _, _, _>(num(11), packet)(input)
+        let event = self.packet();
+        if event.is_err() {
+            // Stop after the first error, since the position in the stream is no longer meaningful.
+            self.open.clear();
+        }
+        Some(event)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct BitSlice<I> {
-    input: I,
-    start_offset: usize,
-    end_offset: usize,
+/// Reads individual bits from a stream of hex digits, ignoring whitespace.
+struct BitReader<R> {
+    bytes: io::Bytes<BufReader<R>>,
+    nibble: u8,
+    remaining: u8,
+    position: usize,
 }
 
-impl<I> From<I> for BitSlice<I> {
-    fn from(input: I) -> Self {
+impl<R: Read> BitReader<R> {
+    fn new(reader: R) -> Self {
         Self {
-            input,
-            start_offset: 0,
-            end_offset: 0,
+            bytes: BufReader::new(reader).bytes(),
+            nibble: 0,
+            remaining: 0,
+            position: 0,
+        }
+    }
+
+    fn read(&mut self, count: usize) -> io::Result<usize> {
+        let mut acc = 0;
+        for _ in 0..count {
+            if self.remaining == 0 {
+                self.nibble = self.next_nibble()?;
+                self.remaining = 4;
+            }
+            self.remaining -= 1;
+            self.position += 1;
+            acc = (acc << 1) | usize::from((self.nibble >> self.remaining) & 1);
+        }
+        Ok(acc)
+    }
+
+    fn next_nibble(&mut self) -> io::Result<u8> {
+        loop {
+            let byte = self
+                .bytes
+                .next()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))??;
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+            return char::from(byte)
+                .to_digit(16)
+                .map(|d| d as u8)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid hex digit"));
         }
     }
 }
 
-impl<I> InputLength for BitSlice<I>
+fn version_sum_events<I>(events: I) -> io::Result<usize>
 where
-    I: InputLength,
+    I: IntoIterator<Item = io::Result<Event>>,
 {
-    fn input_len(&self) -> usize {
-        self.input.input_len() * 8 - (self.start_offset + self.end_offset)
+    let mut sum = 0;
+    for event in events {
+        match event? {
+            Event::Start { version, .. } | Event::Literal { version, .. } => {
+                sum += version as usize
+            }
+            Event::End => {}
+        }
     }
+    Ok(sum)
 }
 
-fn slice<I, E: ParseError<BitSlice<I>>>(
-    count: usize,
-) -> impl Fn(BitSlice<I>) -> IResult<BitSlice<I>, BitSlice<I>, E>
+fn eval_events<T, I>(events: I) -> Result<T, StreamError>
 where
-    I: Slice<RangeTo<usize>> + Slice<RangeFrom<usize>> + InputLength,
+    T: Value,
+    I: IntoIterator<Item = io::Result<Event>>,
 {
-    move |slice: BitSlice<I>| {
-        if slice.input_len() < count {
-            return Err(NomErr::Error(E::from_error_kind(slice, ErrorKind::Eof)));
+    let mut open: Vec<Accumulator<T>> = Vec::new();
+    for event in events {
+        let value = match event? {
+            Event::Start { op_type, .. } => {
+                open.push(Accumulator::new(op_type));
+                continue;
+            }
+            Event::Literal { value, .. } => T::from_literal(&value).ok_or(Overflow)?,
+            Event::End => open
+                .pop()
+                .expect("unmatched end event")
+                .finish()
+                .map_err(EvalError::from)?,
+        };
+        match open.last_mut() {
+            Some(parent) => parent.push(value).map_err(EvalError::from)?,
+            None => return Ok(value),
         }
+    }
+    Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+}
 
-        let split_offset = (count + slice.start_offset) % 8;
-        let num_bytes = (count + slice.start_offset) / 8;
-        let head_bytes = num_bytes + if split_offset != 0 { 1 } else { 0 };
-
-        let head = BitSlice {
-            input: slice.input.slice(..head_bytes),
-            start_offset: slice.start_offset,
-            end_offset: if split_offset == 0 {
-                0
-            } else {
-                8 - split_offset
-            },
-        };
+#[derive(Debug)]
+enum StreamError {
+    Io(io::Error),
+    Eval(EvalError),
+}
 
-        let rest = BitSlice {
-            input: slice.input.slice(num_bytes..),
-            start_offset: split_offset,
-            end_offset: slice.end_offset,
-        };
+impl From<io::Error> for StreamError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
 
-        Ok((rest, head))
+impl From<EvalError> for StreamError {
+    fn from(err: EvalError) -> Self {
+        Self::Eval(err)
     }
 }
 
-fn num<I, O, E: ParseError<BitSlice<I>>>(
-    count: usize,
-) -> impl Fn(BitSlice<I>) -> IResult<BitSlice<I>, O, E>
-where
-    I: Slice<RangeFrom<usize>> + InputIter<Item = u8> + InputLength,
-    O: From<u8> + AddAssign + Shl<usize, Output = O> + Shr<usize, Output = O>,
-{
-    move |slice: BitSlice<I>| {
-        if count == 0 {
-            return Ok((slice, 0u8.into()));
-        }
+impl From<Overflow> for StreamError {
+    fn from(err: Overflow) -> Self {
+        Self::Eval(err.into())
+    }
+}
 
-        if slice.input_len() < count {
-            return Err(NomErr::Error(E::from_error_kind(slice, ErrorKind::Eof)));
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't read transmission: {}", err),
+            Self::Eval(err) => err.fmt(f),
         }
+    }
+}
 
-        let mut acc = 0u8.into();
-        let mut offset = slice.start_offset;
-        let mut remaining = count;
-        let mut new_offset = 0;
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Eval(err) => Some(err),
+        }
+    }
+}
 
-        let num_bytes = (count + slice.start_offset) / 8;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for byte in slice.input.iter_elements().take(num_bytes + 1) {
-            if remaining == 0 {
-                break;
-            }
+    fn events(input: &str) -> Vec<Event> {
+        Decoder::new(input.as_bytes())
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap()
+    }
 
-            let val: O = if offset == 0 {
-                byte.into()
-            } else {
-                ((byte << offset) as u8 >> offset).into()
-            };
+    fn literal(version: u8, value: u64) -> Event {
+        Event::Literal {
+            version,
+            value: BigUint::from(value),
+        }
+    }
 
-            if remaining < 8 - offset {
-                acc += val >> (8 - offset - remaining);
-                new_offset = remaining + offset;
-                break;
-            }
+    #[test]
+    fn literal_decodes() {
+        assert_eq!(vec![literal(6, 2021)], events("D2FE28"));
+    }
 
-            acc += val << (remaining - (8 - offset));
-            remaining -= 8 - offset;
-            offset = 0;
-        }
+    #[test]
+    fn length_type_0_decodes() {
+        let expected = vec![
+            Event::Start {
+                version: 1,
+                op_type: OperatorType::LessThan,
+            },
+            literal(6, 10),
+            literal(2, 20),
+            Event::End,
+        ];
+        assert_eq!(expected, events("38006F45291200"));
+    }
 
-        Ok((
-            BitSlice {
-                input: slice.input.slice(num_bytes..),
-                start_offset: new_offset,
-                end_offset: slice.end_offset,
+    #[test]
+    fn length_type_1_decodes() {
+        let expected = vec![
+            Event::Start {
+                version: 7,
+                op_type: OperatorType::Maximum,
             },
-            acc,
-        ))
+            literal(2, 1),
+            literal(4, 2),
+            literal(1, 3),
+            Event::End,
+        ];
+        assert_eq!(expected, events("EE00D40C823060"));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn bits_are_read_across_nibbles() {
+        let mut bits = BitReader::new("C3 5".as_bytes());
+        assert_eq!(0b110, bits.read(3).unwrap());
+        assert_eq!(0b000110, bits.read(6).unwrap());
+        assert_eq!(0b101, bits.read(3).unwrap());
+        assert_eq!(12, bits.position);
+        assert_eq!(
+            io::ErrorKind::UnexpectedEof,
+            bits.read(1).unwrap_err().kind()
+        );
+    }
 
     #[test]
-    fn hex_string_parses() {
-        let input = "D2FE28";
-        assert_eq!(Ok(("", vec![0xd2, 0xfe, 0x28])), hex_string(input));
+    fn streamed_version_sums() {
+        let inputs = [
+            ("8A004A801A8002F478\n", 16),
+            ("620080001611562C8802118E34\n", 12),
+            ("C0015000016115A2E0802F182340\n", 23),
+            ("A0016C880162017C3686B18A3D4780\n", 31),
+        ];
+
+        for (input, expected) in inputs {
+            let sum = version_sum_events(Decoder::new(input.as_bytes())).unwrap();
+            assert_eq!(expected, sum);
+        }
     }
 
     #[test]
-    fn literal_parses() {
-        let input = BitSlice::from(&[0xd2, 0xfe, 0x28][..]);
-        let expected = Packet::literal(6, 2021);
-        let actual = packet(input).unwrap().1;
-        assert_eq!(expected, actual);
+    fn streamed_evals() {
+        let inputs = [
+            ("C200B40A82", 3),
+            ("04005AC33890", 54),
+            ("880086C3E88112", 7),
+            ("CE00C43D881120", 9),
+            ("D8005AC2A8F0", 1),
+            ("F600BC2D8F", 0),
+            ("9C005AC2F8F0", 0),
+            ("9C0141080250320F1802104A08", 1),
+        ];
+
+        for (input, expected) in inputs {
+            let eval: usize = eval_events(Decoder::new(input.as_bytes())).unwrap();
+            assert_eq!(expected, eval);
+            let eval: BigUint = eval_events(Decoder::new(input.as_bytes())).unwrap();
+            assert_eq!(BigUint::from(expected), eval);
+        }
     }
 
     #[test]
-    fn length_type_0_parses() {
-        let input = BitSlice::from(&[0x38, 0x00, 0x6F, 0x45, 0x29, 0x12, 0x00][..]);
-        let expected = Packet::operator(
-            1,
-            OperatorType::LessThan,
-            vec![Packet::literal(6, 10), Packet::literal(2, 20)],
-        );
-        let actual = packet(input).unwrap().1;
-        assert_eq!(expected, actual);
+    fn streamed_product_overflow_is_reported() {
+        // The product of two literals of 2^40.
+        let input = "0600848C210842108400246108421084200";
+        let result = eval_events::<usize, _>(Decoder::new(input.as_bytes()));
+        assert!(matches!(
+            result,
+            Err(StreamError::Eval(EvalError::Overflow(Overflow)))
+        ));
+        let eval: BigUint = eval_events(Decoder::new(input.as_bytes())).unwrap();
+        assert_eq!(BigUint::one() << 80u8, eval);
     }
 
     #[test]
-    fn length_type_1_parses() {
-        let input = BitSlice::from(&[0xEE, 0x00, 0xD4, 0x0C, 0x82, 0x30, 0x60][..]);
-        let expected = Packet::operator(
-            7,
-            OperatorType::Maximum,
-            vec![
-                Packet::literal(2, 1),
-                Packet::literal(4, 2),
-                Packet::literal(1, 3),
-            ],
-        );
-        let actual = packet(input).unwrap().1;
-        assert_eq!(expected, actual);
-    }
-
-    // #[test]
-    // fn test_sums() {
-    //     let inputs = [
-    //         ("C200B40A82", 3),
-    //         ("04005AC33890", 54),
-    //         ("880086C3E88112", 7),
-    //         ("CE00C43D881120", 9),
-    //         ("D8005AC2A8F0", 1),
-    //         ("F600BC2D8F", 0),
-    //         ("9C005AC2F8F0", 0),
-    //         ("9C0141080250320F1802104A08", 1),
-    //     ];
-
-    //     for (input, expected) in inputs {
-    //         let packet = parse(input);
-    //         assert_eq!(expected, packet.eval());
-    //     }
-    // }
+    fn streamed_wide_literal_evals() {
+        let input = "123084210842108421084020";
+        let result = eval_events::<usize, _>(Decoder::new(input.as_bytes()));
+        assert!(matches!(
+            result,
+            Err(StreamError::Eval(EvalError::Overflow(Overflow)))
+        ));
+        let eval: BigUint = eval_events(Decoder::new(input.as_bytes())).unwrap();
+        assert_eq!((BigUint::one() << 64u8) + 1u8, eval);
+    }
 
     #[test]
-    fn less_than_byte_num_parses() {
-        let input = BitSlice {
-            input: &[0b11000000][..],
-            start_offset: 0,
-            end_offset: 0,
-        };
-        let (rest, n) = num::<&[u8], u8, ()>(3)(input).unwrap();
-        assert_eq!(0b110, n);
-        assert_eq!(
-            BitSlice {
-                input: &[0b11000000][..],
-                start_offset: 3,
-                end_offset: 0,
-            },
-            rest
-        );
+    fn streamed_wrong_arity_is_an_error() {
+        let inputs = [
+            // A less-than packet whose only subpacket is the literal 10.
+            ("38002F450", OperatorType::LessThan, 1),
+            // A greater-than packet whose only subpacket is the literal 5.
+            ("16004428", OperatorType::GreaterThan, 1),
+            // An equal-to packet with three literal 1 subpackets.
+            ("1E00C40881102", OperatorType::EqualTo, 3),
+            // A minimum packet with no subpackets.
+            ("0A000", OperatorType::Minimum, 0),
+        ];
+
+        for (input, op_type, subpackets) in inputs {
+            let arity = Arity {
+                op_type,
+                subpackets,
+            };
+            let result = eval_events::<usize, _>(Decoder::new(input.as_bytes()));
+            assert!(matches!(
+                result,
+                Err(StreamError::Eval(EvalError::Arity(err))) if err == arity
+            ));
+            let result = eval_events::<BigUint, _>(Decoder::new(input.as_bytes()));
+            assert!(matches!(
+                result,
+                Err(StreamError::Eval(EvalError::Arity(err))) if err == arity
+            ));
+        }
     }
 
     #[test]
-    fn slice_offset() {
-        let input = BitSlice {
-            input: &[0, 1, 2, 3][..],
-            start_offset: 6,
-            end_offset: 3,
+    fn arity_errors_are_described() {
+        let arity = Arity {
+            op_type: OperatorType::EqualTo,
+            subpackets: 3,
         };
-        let (rest, head) = slice::<&[u8], ()>(9)(input).unwrap();
         assert_eq!(
-            BitSlice {
-                input: &[0, 1][..],
-                start_offset: 6,
-                end_offset: 1,
-            },
-            head
+            "EqualTo packet needs exactly 2 subpackets but has 3",
+            arity.to_string()
         );
+
+        let arity = Arity {
+            op_type: OperatorType::Minimum,
+            subpackets: 0,
+        };
         assert_eq!(
-            BitSlice {
-                input: &[1, 2, 3][..],
-                start_offset: 7,
-                end_offset: 3,
-            },
-            rest
+            "Minimum packet needs at least 1 subpackets but has 0",
+            arity.to_string()
         );
     }
 
     #[test]
-    fn zero_slice_offset() {
-        let input = BitSlice {
-            input: &[0, 1, 2, 3][..],
-            start_offset: 6,
-            end_offset: 3,
-        };
-        let (rest, head) = slice::<&[u8], ()>(10)(input).unwrap();
-        assert_eq!(
-            BitSlice {
-                input: &[0, 1][..],
-                start_offset: 6,
-                end_offset: 0,
-            },
-            head
-        );
-        assert_eq!(
-            BitSlice {
-                input: &[2, 3][..],
-                start_offset: 0,
-                end_offset: 3,
-            },
-            rest
+    fn truncated_stream_is_an_error() {
+        let result = eval_events::<usize, _>(Decoder::new("38006F4529".as_bytes()));
+        assert!(
+            matches!(result, Err(StreamError::Io(err)) if err.kind() == io::ErrorKind::UnexpectedEof)
         );
     }
+
+    #[test]
+    fn invalid_hex_is_an_error() {
+        let mut decoder = Decoder::new("D2FG28".as_bytes());
+        let err = decoder.next().unwrap().unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert!(decoder.next().is_none());
+    }
 }
@@ -1,7 +1,9 @@
-use super::utils::Vec2d;
+use super::utils;
 use nom::{character::complete::line_ending, multi::separated_list0, IResult};
 use std::ops;
 
+type Point = utils::Point<i64, 3>;
+
 pub fn star_1(data: String) {
     use std::collections::HashSet;
 
@@ -12,7 +14,7 @@ pub fn star_1(data: String) {
     for idx in 0..sensors.len() {
         let (off, rot) = off_rots[idx].unwrap();
         for point in &sensors[idx] {
-            all_points.insert(off + point.rotated(rot));
+            all_points.insert(off + rot.apply(*point));
         }
     }
 
@@ -43,7 +45,7 @@ fn check_overlap(a: &[Point], b: &[Point]) -> Option<(Rotation, Point)> {
         let mut offset_counts = HashMap::new();
         for p_a in a {
             for p_b in b {
-                *offset_counts.entry(*p_a - p_b.rotated(rot)).or_insert(0) += 1;
+                *offset_counts.entry(*p_a - rot.apply(*p_b)).or_insert(0) += 1;
             }
         }
         for (offset, count) in offset_counts {
@@ -57,7 +59,7 @@ fn check_overlap(a: &[Point], b: &[Point]) -> Option<(Rotation, Point)> {
 
 fn off_rots(sensors: &[Vec<Point>]) -> Vec<Option<(Point, Rotation)>> {
     let mut off_rots = vec![None; sensors.len()];
-    off_rots[0] = Some((Point { x: 0, y: 0, z: 0 }, NULL_ROTATION));
+    off_rots[0] = Some((Point::new(0, 0, 0), NULL_ROTATION));
 
     let mut in_progress = true;
     while in_progress {
@@ -78,7 +80,7 @@ fn off_rots(sensors: &[Vec<Point>]) -> Vec<Option<(Point, Rotation)>> {
                 let b = &sensors[idx_b];
 
                 if let Some((rot, off)) = check_overlap(a, b) {
-                    off_rots[idx_b] = Some((a_off + off.rotated(a_rot), rot + a_rot));
+                    off_rots[idx_b] = Some((a_off + a_rot.apply(off), rot + a_rot));
                     in_progress = true;
                 }
             }
@@ -116,73 +118,6 @@ fn sensor_header(input: &str) -> IResult<&str, u64> {
     Ok((input, n))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct Point {
-    x: i64,
-    y: i64,
-    z: i64,
-}
-
-impl Point {
-    fn rotated(self, rot: Rotation) -> Self {
-        Self {
-            x: rot.x.sign.apply(self.axis(rot.x.axis)),
-            y: rot.y.sign.apply(self.axis(rot.y.axis)),
-            z: rot.z.sign.apply(self.axis(rot.z.axis)),
-        }
-    }
-
-    fn axis(self, axis: Axis) -> i64 {
-        match axis {
-            Axis::X => self.x,
-            Axis::Y => self.y,
-            Axis::Z => self.z,
-        }
-    }
-
-    fn manhattan_distance(self, rhs: Self) -> u64 {
-        (self.x - rhs.x).abs() as u64
-            + (self.y - rhs.y).abs() as u64
-            + (self.z - rhs.z).abs() as u64
-    }
-}
-
-impl ops::Neg for Point {
-    type Output = Self;
-
-    fn neg(self) -> Self {
-        Self {
-            x: -self.x,
-            y: -self.y,
-            z: -self.z,
-        }
-    }
-}
-
-impl ops::Add for Point {
-    type Output = Self;
-
-    fn add(self, rhs: Self) -> Self {
-        Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-            z: self.z + rhs.z,
-        }
-    }
-}
-
-impl ops::Sub for Point {
-    type Output = Self;
-
-    fn sub(self, rhs: Self) -> Self {
-        Self {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-            z: self.z - rhs.z,
-        }
-    }
-}
-
 fn point(input: &str) -> IResult<&str, Point> {
     use nom::character::complete::{char as char_, i64 as i64_};
 
@@ -191,7 +126,7 @@ fn point(input: &str) -> IResult<&str, Point> {
     let (input, y) = i64_(input)?;
     let (input, _) = char_(',')(input)?;
     let (input, z) = i64_(input)?;
-    Ok((input, Point { x, y, z }))
+    Ok((input, Point::new(x, y, z)))
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -202,6 +137,11 @@ struct Rotation {
 }
 
 impl Rotation {
+    fn apply(self, point: Point) -> Point {
+        let coord = |signed: SignedAxis| signed.sign.apply(signed.axis.of(point));
+        Point::new(coord(self.x), coord(self.y), coord(self.z))
+    }
+
     fn axis(self, axis: Axis) -> SignedAxis {
         match axis {
             Axis::X => self.x,
@@ -428,3 +368,13 @@ enum Axis {
     Y,
     Z,
 }
+
+impl Axis {
+    fn of(self, point: Point) -> i64 {
+        match self {
+            Self::X => point.x(),
+            Self::Y => point.y(),
+            Self::Z => point.z(),
+        }
+    }
+}
@@ -1,22 +1,27 @@
+use super::utils::{BoundingBox, Point, Simulation};
 use nom::IResult;
 use std::collections::HashSet;
 
+type Coord = Point<i64, 2>;
+
 pub fn star_1(data: String) {
-    let Info {
-        pixel_map,
-        mut image,
-    } = parse(&data);
-    let image = image.step_n(&pixel_map[..], 2);
-    println!("{}", image.num_lit());
+    let Info { pixel_map, image } = parse(&data);
+    let mut enhancement = Enhancement {
+        pixel_map: &pixel_map,
+        image,
+    };
+    enhancement.run(2);
+    println!("{}", enhancement.image.num_lit());
 }
 
 pub fn star_2(data: String) {
-    let Info {
-        pixel_map,
-        mut image,
-    } = parse(&data);
-    let image = image.step_n(&pixel_map[..], 50);
-    println!("{}", image.num_lit());
+    let Info { pixel_map, image } = parse(&data);
+    let mut enhancement = Enhancement {
+        pixel_map: &pixel_map,
+        image,
+    };
+    enhancement.run(50);
+    println!("{}", enhancement.image.num_lit());
 }
 
 fn parse(input: &str) -> Info {
@@ -40,103 +45,82 @@ fn info(input: &str) -> IResult<&str, Info> {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Image {
-    pixels: HashSet<(i64, i64)>,
-    x_min: i64,
-    x_max: i64,
-    y_min: i64,
-    y_max: i64,
+    pixels: HashSet<Coord>,
+    bounds: BoundingBox<i64, 2>,
     ambient: Pixel,
 }
 
-impl Image {
-    fn step_n(&self, pixel_map: &[Pixel], num: usize) -> Self {
-        let mut image = self.clone();
-        for _ in 0..num {
-            image = image.step(pixel_map);
-        }
-        image
+/// Repeatedly enhances an image with the same pixel map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Enhancement<'a> {
+    pixel_map: &'a [Pixel],
+    image: Image,
+}
+
+impl Simulation for Enhancement<'_> {
+    fn step(&mut self) {
+        self.image = self.image.step(self.pixel_map);
     }
+}
 
+impl Image {
     fn step(&self, pixel_map: &[Pixel]) -> Self {
         let mut image = Image {
             pixels: HashSet::new(),
-            x_min: self.x_min - 1,
-            x_max: self.x_max + 1,
-            y_min: self.y_min - 1,
-            y_max: self.y_max + 1,
+            bounds: self.bounds.expanded(1),
             ambient: if self.ambient == Pixel::Light {
                 pixel_map[511]
             } else {
                 pixel_map[0]
             },
         };
-        for y in image.y_min..=image.y_max {
-            for x in image.x_min..=image.x_max {
-                if pixel_map[self.coord_to_lookup((x, y))] == Pixel::Light {
-                    image.pixels.insert((x, y));
-                }
+        for coord in image.bounds.points() {
+            if pixel_map[self.coord_to_lookup(coord)] == Pixel::Light {
+                image.pixels.insert(coord);
             }
         }
         image
     }
 
-    fn coord_to_lookup(&self, coord: (i64, i64)) -> usize {
-        [
-            (-1, -1),
-            (0, -1),
-            (1, -1),
-            (-1, 0),
-            (0, 0),
-            (1, 0),
-            (-1, 1),
-            (0, 1),
-            (1, 1),
-        ]
-        .into_iter()
-        .enumerate()
-        .map(|(i, (x, y))| match self.pixel((coord.0 + x, coord.1 + y)) {
-            Pixel::Dark => 0,
-            Pixel::Light => 1 << (8 - i),
-        })
-        .sum()
+    fn coord_to_lookup(&self, coord: Coord) -> usize {
+        coord
+            .neighborhood()
+            .enumerate()
+            .map(|(i, coord)| match self.pixel(coord) {
+                Pixel::Dark => 0,
+                Pixel::Light => 1 << (8 - i),
+            })
+            .sum()
     }
 
-    fn pixel(&self, coord: (i64, i64)) -> Pixel {
+    fn pixel(&self, coord: Coord) -> Pixel {
         if self.pixels.contains(&coord) {
             Pixel::Light
-        } else if self.is_in_bounds(coord) {
+        } else if self.bounds.contains(coord) {
             Pixel::Dark
         } else {
             self.ambient
         }
     }
 
-    fn is_in_bounds(&self, (x, y): (i64, i64)) -> bool {
-        x >= self.x_min && x <= self.x_max && y >= self.y_min && y <= self.y_max
-    }
-
     fn num_lit(&self) -> usize {
         self.pixels.iter().count()
     }
 
     fn print(&self) {
-        for y in self.y_min..=self.y_max {
-            for x in self.x_min..=self.x_max {
-                print!("{}", self.pixel((x, y)).as_char());
+        for coord in self.bounds.points() {
+            print!("{}", self.pixel(coord).as_char());
+            if coord.x() == self.bounds.max.x() {
+                println!();
             }
-            println!();
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 struct ImageBuilder {
-    pixels: HashSet<(i64, i64)>,
-    x_min: i64,
-    x_max: i64,
-    y_min: i64,
-    y_max: i64,
-    ambient: Pixel,
+    pixels: HashSet<Coord>,
+    bounds: Option<BoundingBox<i64, 2>>,
 }
 
 impl ImageBuilder {
@@ -144,34 +128,21 @@ impl ImageBuilder {
         Self::default()
     }
 
-    fn set_pixel(&mut self, (x, y): (i64, i64)) {
-        self.pixels.insert((x, y));
-        self.x_min = self.x_min.min(x);
-        self.x_max = self.x_max.max(x);
-        self.y_min = self.y_min.min(y);
-        self.y_max = self.y_max.max(y);
+    fn set_pixel(&mut self, coord: Coord) {
+        self.pixels.insert(coord);
+        self.bounds = Some(match self.bounds {
+            Some(bounds) => bounds.including(coord),
+            None => BoundingBox::new(coord, coord),
+        });
     }
 
     fn build(self) -> Image {
+        let origin = Coord::new(0, 0);
         Image {
             pixels: self.pixels,
-            x_min: self.x_min,
-            x_max: self.x_max,
-            y_min: self.y_min,
-            y_max: self.y_max,
-            ambient: self.ambient,
-        }
-    }
-}
-
-impl Default for ImageBuilder {
-    fn default() -> Self {
-        Self {
-            pixels: HashSet::new(),
-            x_min: i64::MAX,
-            x_max: i64::MIN,
-            y_min: i64::MAX,
-            y_max: i64::MIN,
+            bounds: self
+                .bounds
+                .unwrap_or_else(|| BoundingBox::new(origin, origin)),
             ambient: Pixel::Dark,
         }
     }
@@ -195,7 +166,7 @@ fn image(mut input: &str) -> IResult<&str, Image> {
             };
             input = inp;
             if p == Pixel::Light {
-                builder.set_pixel((x, y));
+                builder.set_pixel(Coord::new(x, y));
             }
         }
     }
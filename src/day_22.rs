@@ -1,6 +1,6 @@
 use itertools::iproduct;
 use nom::{bytes::complete::tag, IResult};
-use std::{fmt, ops};
+use std::fmt;
 
 pub fn star_1(data: String) {
     let steps = parse(&data);
@@ -135,7 +135,7 @@ impl Cube {
         Some(Cube { x, y, z })
     }
 
-    fn non_overlapping(self, rhs: Self) -> impl Iterator<Item = Self> {
+    fn non_overlapping(self, rhs: Self) -> impl Iterator<Item = Self> + Clone {
         let xs = self.x.parts(rhs.x);
         let ys = self.y.parts(rhs.y).enumerate();
         let zs = self.z.parts(rhs.z).enumerate();
@@ -235,27 +235,24 @@ impl Segment {
         rhs.end() >= self.start && rhs.start <= self.end()
     }
 
-    fn parts(self, rhs: Self) -> SegmentPartsIter {
-        SegmentPartsIter::new(self, rhs)
+    fn parts(self, rhs: Self) -> impl Iterator<Item = Overlap<Segment>> + Clone {
+        let ovr = self.overlapping(rhs).map(Overlap::overlap);
+        let non_ovr = self.non_overlapping(rhs).map(Overlap::non_overlap);
+        ovr.into_iter().chain(non_ovr)
     }
 
     fn overlapping(self, rhs: Self) -> Option<Self> {
-        // TODO: Break this down into calculating endpoints.
-        if rhs.start <= self.start && rhs.end() >= self.start && rhs.end() < self.end() {
-            Some(Self::from_ends(self.start, rhs.end()))
-        } else if rhs.start > self.start && rhs.end() < self.end() {
-            Some(Self::from_ends(rhs.start, rhs.end()))
-        } else if rhs.start > self.start && rhs.start <= self.end() && rhs.end() >= self.end() {
-            Some(Self::from_ends(rhs.start, self.end()))
-        } else if rhs.start <= self.start && rhs.end() >= self.end() {
-            Some(self)
-        } else {
-            None
-        }
+        let (start, end) = (self.start.max(rhs.start), self.end().min(rhs.end()));
+        (start <= end).then(|| Self::from_ends(start, end))
     }
 
-    fn non_overlapping(self, rhs: Self) -> SegmentNonOverlappingIter {
-        SegmentNonOverlappingIter::new(self, rhs)
+    /// The parts of `self` before and after `rhs`, in ascending order.
+    fn non_overlapping(self, rhs: Self) -> impl Iterator<Item = Self> + Clone {
+        let before = (rhs.start > self.start)
+            .then(|| Self::from_ends(self.start, (rhs.start - 1).min(self.end())));
+        let after = (rhs.end() < self.end())
+            .then(|| Self::from_ends((rhs.end() + 1).max(self.start), self.end()));
+        before.into_iter().chain(after)
     }
 
     fn try_adding(self, rhs: Self) -> Option<Self> {
@@ -276,104 +273,6 @@ impl Segment {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct SegmentPartsIter {
-    ovr: Option<Segment>,
-    non_ovr: SegmentNonOverlappingIter,
-    idx: usize,
-}
-
-impl SegmentPartsIter {
-    fn new(a: Segment, b: Segment) -> Self {
-        let ovr = a.overlapping(b);
-        let non_ovr = SegmentNonOverlappingIter::new(a, b);
-        Self {
-            ovr,
-            non_ovr,
-            idx: 0,
-        }
-    }
-}
-
-impl Iterator for SegmentPartsIter {
-    type Item = Overlap<Segment>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.idx {
-                0 => {
-                    self.idx += 1;
-                    if let Some(ovr) = self.ovr {
-                        return Some(Overlap::overlap(ovr));
-                    }
-                }
-                1 => {
-                    if let Some(next) = self.non_ovr.next() {
-                        return Some(Overlap::non_overlap(next));
-                    }
-                    self.idx += 1;
-                }
-                _ => return None,
-            }
-        }
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct SegmentNonOverlappingIter {
-    left: Option<Segment>,
-    right: Option<Segment>,
-    full: Option<Segment>,
-    idx: usize,
-}
-
-impl SegmentNonOverlappingIter {
-    fn new(a: Segment, b: Segment) -> Self {
-        let left = (b.start > a.start && b.start <= a.end())
-            .then(move || Segment::from_ends(a.start, b.start - 1));
-        let right = (b.end() >= a.start && b.end() < a.end())
-            .then(move || Segment::from_ends(b.end() + 1, a.end()));
-        let full = (b.end() < a.start || b.start > a.end()).then(|| a);
-
-        Self {
-            left,
-            right,
-            full,
-            idx: 0,
-        }
-    }
-}
-
-impl Iterator for SegmentNonOverlappingIter {
-    type Item = Segment;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.idx {
-                0 => {
-                    self.idx += 1;
-                    if let Some(left) = self.left {
-                        return Some(left);
-                    }
-                }
-                1 => {
-                    self.idx += 1;
-                    if let Some(right) = self.right {
-                        return Some(right);
-                    }
-                }
-                2 => {
-                    self.idx += 1;
-                    if let Some(full) = self.full {
-                        return Some(full);
-                    }
-                }
-                _ => return None,
-            }
-        }
-    }
-}
-
 impl fmt::Display for Segment {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}..{}", self.start, self.end())
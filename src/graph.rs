@@ -0,0 +1,162 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+/// A directed graph stored as adjacency sets.
+///
+/// Undirected graphs are represented by adding each edge in both directions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph<N: Eq + Hash> {
+    edges: HashMap<N, HashSet<N>>,
+}
+
+impl<N: Copy + Eq + Hash> Graph<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, node: N) {
+        self.edges.entry(node).or_default();
+    }
+
+    pub fn add_edge(&mut self, start: N, end: N) {
+        self.edges.entry(start).or_default().insert(end);
+        self.add_node(end);
+    }
+
+    pub fn add_undirected_edge(&mut self, a: N, b: N) {
+        self.add_edge(a, b);
+        self.add_edge(b, a);
+    }
+
+    pub fn neighbors(&self, node: N) -> impl Iterator<Item = N> + '_ {
+        self.edges.get(&node).into_iter().flatten().copied()
+    }
+
+    /// Calls `on_path` with every path from `start` to `end` that `policy` allows.
+    ///
+    /// Paths stop as soon as they reach `end`.
+    pub fn walk_paths<P, F>(&self, start: N, end: N, mut policy: P, mut on_path: F)
+    where
+        P: VisitPolicy<N>,
+        F: FnMut(&[N]),
+    {
+        let mut walk = PathWalk {
+            graph: self,
+            end,
+            visits: HashMap::new(),
+            path: Vec::new(),
+        };
+        walk.visit(start, &mut policy, &mut on_path);
+    }
+
+    pub fn count_paths<P: VisitPolicy<N>>(&self, start: N, end: N, policy: P) -> usize {
+        let mut count = 0;
+        self.walk_paths(start, end, policy, |_| count += 1);
+        count
+    }
+
+    pub fn paths<P: VisitPolicy<N>>(&self, start: N, end: N, policy: P) -> Vec<Vec<N>> {
+        let mut paths = Vec::new();
+        self.walk_paths(start, end, policy, |path| paths.push(path.to_vec()));
+        paths
+    }
+}
+
+impl<N: Eq + Hash> Default for Graph<N> {
+    fn default() -> Self {
+        Self {
+            edges: HashMap::default(),
+        }
+    }
+}
+
+/// Decides which nodes a path may move onto while enumerating paths.
+pub trait VisitPolicy<N> {
+    /// Called before the path moves onto `node`, which it has already passed through `visits`
+    /// times. Returning `false` prunes that branch.
+    fn enter(&mut self, node: N, visits: usize) -> bool;
+
+    /// Called when the path backs off a node that `enter` accepted, with the same `visits`.
+    fn leave(&mut self, _node: N, _visits: usize) {}
+}
+
+impl<N, F> VisitPolicy<N> for F
+where
+    F: FnMut(N, usize) -> bool,
+{
+    fn enter(&mut self, node: N, visits: usize) -> bool {
+        self(node, visits)
+    }
+}
+
+struct PathWalk<'g, N: Eq + Hash> {
+    graph: &'g Graph<N>,
+    end: N,
+    visits: HashMap<N, usize>,
+    path: Vec<N>,
+}
+
+impl<'g, N: Copy + Eq + Hash> PathWalk<'g, N> {
+    fn visit<P, F>(&mut self, node: N, policy: &mut P, on_path: &mut F)
+    where
+        P: VisitPolicy<N>,
+        F: FnMut(&[N]),
+    {
+        let visits = self.visits.get(&node).copied().unwrap_or(0);
+        if !policy.enter(node, visits) {
+            return;
+        }
+        self.path.push(node);
+        *self.visits.entry(node).or_insert(0) += 1;
+
+        if node == self.end {
+            on_path(&self.path);
+        } else {
+            let graph = self.graph;
+            for next in graph.neighbors(node) {
+                self.visit(next, policy, on_path);
+            }
+        }
+
+        *self.visits.get_mut(&node).unwrap() -= 1;
+        self.path.pop();
+        policy.leave(node, visits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn undirected(edges: &[(u32, u32)]) -> Graph<u32> {
+        let mut graph = Graph::new();
+        for &(a, b) in edges {
+            graph.add_undirected_edge(a, b);
+        }
+        graph
+    }
+
+    #[test]
+    fn simple_paths_are_enumerated() {
+        let graph = undirected(&[(0, 1), (0, 2), (1, 2), (1, 3), (2, 3)]);
+        let mut paths = graph.paths(0, 3, |_, visits| visits == 0);
+        paths.sort();
+        let expected = vec![
+            vec![0, 1, 2, 3],
+            vec![0, 1, 3],
+            vec![0, 2, 1, 3],
+            vec![0, 2, 3],
+        ];
+        assert_eq!(expected, paths);
+    }
+
+    #[test]
+    fn closure_policies_work() {
+        let graph = undirected(&[(0, 1), (1, 2)]);
+        let count = graph.count_paths(0, 2, |node, visits| node == 1 || visits == 0);
+        // Node 1 may be repeated, but only by going back through 0, which may not.
+        assert_eq!(1, count);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{env, fs::File, io::Read};
+use std::{env, error::Error, fs::File, io::Read, process};
 
 mod day_01;
 mod day_02;
@@ -25,64 +25,90 @@ mod day_22;
 // mod day_23;
 // mod day_24;
 // mod day_25;
+mod graph;
 mod utils;
 
+/// A star that may fail.
+type Fallible = fn(String) -> Result<(), Box<dyn Error>>;
+
+/// A star that also takes any arguments after the input file, and may fail.
+type WithArgs = fn(String, &[String]) -> Result<(), Box<dyn Error>>;
+
+/// What to run for a day and star.
+enum Command {
+    Plain(fn(String)),
+    Fallible(Fallible),
+    WithArgs(WithArgs),
+}
+
 fn main() {
     let mut args = env::args();
     let _name = args.next().unwrap();
     let day = args.next().unwrap().parse::<u32>().unwrap();
     let star = args.next().unwrap().parse::<u32>().unwrap();
     let filename = args.next().unwrap();
+    let extra_args = args.collect::<Vec<_>>();
 
     let mut data = String::new();
     let mut file = File::open(filename).unwrap();
     file.read_to_string(&mut data).unwrap();
 
-    let func = match (day, star) {
-        (1, 1) => day_01::star_1,
-        (1, 2) => day_01::star_2,
-        (2, 1) => day_02::star_1,
-        (2, 2) => day_02::star_2,
-        (3, 1) => day_03::star_1,
-        (3, 2) => day_03::star_2,
-        (4, 1) => day_04::star_1,
-        (4, 2) => day_04::star_2,
-        (5, 1) => day_05::star_1,
-        (5, 2) => day_05::star_2,
-        (6, 1) => day_06::star_1,
-        (6, 2) => day_06::star_2,
-        (7, 1) => day_07::star_1,
-        (7, 2) => day_07::star_2,
-        (8, 1) => day_08::star_1,
-        (8, 2) => day_08::star_2,
-        (9, 1) => day_09::star_1,
-        (9, 2) => day_09::star_2,
-        (10, 1) => day_10::star_1,
-        (10, 2) => day_10::star_2,
-        (11, 1) => day_11::star_1,
-        (11, 2) => day_11::star_2,
-        (12, 1) => day_12::star_1,
-        (12, 2) => day_12::star_2,
-        (13, 1) => day_13::star_1,
-        (13, 2) => day_13::star_2,
-        (14, 1) => day_14::star_1,
-        (14, 2) => day_14::star_2,
-        (15, 1) => day_15::star_1,
-        (15, 2) => day_15::star_2,
-        (16, 1) => day_16::star_1,
-        (16, 2) => day_16::star_2,
-        (17, 1) => day_17::star_1,
-        (17, 2) => day_17::star_2,
-        (18, 1) => day_18::star_1,
-        (18, 2) => day_18::star_2,
-        (19, 1) => day_19::star_1,
-        (19, 2) => day_19::star_2,
-        (20, 1) => day_20::star_1,
-        (20, 2) => day_20::star_2,
-        (21, 1) => day_21::star_1,
-        (21, 2) => day_21::star_2,
-        (22, 1) => day_22::star_1,
-        (22, 2) => day_22::star_2,
+    let command = match (day, star) {
+        (1, 1) => Command::WithArgs(day_01::star_1),
+        (1, 2) => Command::WithArgs(day_01::star_2),
+        (1, 3) => Command::WithArgs(day_01::summary),
+        (2, 1) => Command::Fallible(day_02::star_1),
+        (2, 2) => Command::Fallible(day_02::star_2),
+        (2, 3) => Command::WithArgs(day_02::trajectory),
+        (3, 1) => Command::Plain(day_03::star_1),
+        (3, 2) => Command::Plain(day_03::star_2),
+        (4, 1) => Command::WithArgs(day_04::star_1),
+        (4, 2) => Command::WithArgs(day_04::star_2),
+        (5, 1) => Command::WithArgs(day_05::star_1),
+        (5, 2) => Command::WithArgs(day_05::star_2),
+        (5, 3) => Command::Plain(day_05::diagram),
+        (6, 1) => Command::Fallible(day_06::star_1),
+        (6, 2) => Command::Fallible(day_06::star_2),
+        (6, 3) => Command::WithArgs(day_06::trajectory),
+        (7, 1) => Command::Plain(day_07::star_1),
+        (7, 2) => Command::Plain(day_07::star_2),
+        (7, 3) => Command::WithArgs(day_07::alignments),
+        (8, 1) => Command::Plain(day_08::star_1),
+        (8, 2) => Command::Plain(day_08::star_2),
+        (8, 3) => Command::Plain(day_08::display),
+        (9, 1) => Command::WithArgs(day_09::star_1),
+        (9, 2) => Command::WithArgs(day_09::star_2),
+        (9, 3) => Command::WithArgs(day_09::low_points_csv),
+        (10, 1) => Command::Fallible(day_10::star_1),
+        (10, 2) => Command::Fallible(day_10::star_2),
+        (10, 3) => Command::WithArgs(day_10::repair),
+        (11, 1) => Command::Plain(day_11::star_1),
+        (11, 2) => Command::Plain(day_11::star_2),
+        (11, 3) => Command::WithArgs(day_11::energy_after),
+        (11, 4) => Command::Fallible(day_11::cycle),
+        (12, 1) => Command::Plain(day_12::star_1),
+        (12, 2) => Command::Plain(day_12::star_2),
+        (12, 3) => Command::WithArgs(day_12::list_paths),
+        (13, 1) => Command::Plain(day_13::star_1),
+        (13, 2) => Command::Plain(day_13::star_2),
+        (14, 1) => Command::Plain(day_14::star_1),
+        (14, 2) => Command::Plain(day_14::star_2),
+        (15, 1) => Command::Plain(day_15::star_1),
+        (15, 2) => Command::Plain(day_15::star_2),
+        (16, 1) => Command::Plain(day_16::star_1),
+        (16, 2) => Command::Plain(day_16::star_2),
+        (17, 1) => Command::Plain(day_17::star_1),
+        (17, 2) => Command::Plain(day_17::star_2),
+        (18, 1) => Command::Plain(day_18::star_1),
+        (18, 2) => Command::Plain(day_18::star_2),
+        (19, 1) => Command::Plain(day_19::star_1),
+        (19, 2) => Command::Plain(day_19::star_2),
+        (20, 1) => Command::Plain(day_20::star_1),
+        (20, 2) => Command::Plain(day_20::star_2),
+        (21, 1) => Command::Plain(day_21::star_1),
+        (21, 2) => Command::Plain(day_21::star_2),
+        (22, 1) => Command::Plain(day_22::star_1),
+        (22, 2) => Command::Plain(day_22::star_2),
         // (23, 1) => day_23::star_1,
         // (23, 2) => day_23::star_2,
         // (24, 1) => day_24::star_1,
@@ -95,5 +121,16 @@ fn main() {
         }
     };
 
-    func(data);
+    match command {
+        Command::Plain(func) => func(data),
+        Command::Fallible(func) => exit_on_error(func(data)),
+        Command::WithArgs(func) => exit_on_error(func(data, &extra_args)),
+    }
+}
+
+fn exit_on_error(result: Result<(), Box<dyn Error>>) {
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
 }
@@ -1,5 +1,6 @@
 use nom::{error::ParseError, Compare, IResult, InputIter, InputLength, Parser, Slice};
-use std::ops::{self, Range, RangeFrom, RangeTo};
+use std::ops::{self, Range, RangeFrom, RangeInclusive, RangeTo};
+use std::{collections::HashMap, hash::Hash};
 
 pub fn lines<I, O, E, F>(parser: F) -> impl FnMut(I) -> IResult<I, Vec<O>, E>
 where
@@ -21,6 +22,39 @@ where
     parser.parse(input).unwrap().1
 }
 
+/// Parses rows of single digits into a grid.
+pub fn digit_grid(input: &str) -> IResult<&str, Vec2d<u8>> {
+    use nom::{
+        branch::alt,
+        character::complete::{anychar, line_ending},
+        combinator::{map, map_opt, value},
+        multi::fold_many0,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum DigitOrNewline {
+        Digit(u8),
+        Newline,
+    }
+
+    let digit = map_opt(anychar, |c| c.to_digit(10).map(|x| x as u8));
+    let digit_or_newline = alt((
+        map(digit, DigitOrNewline::Digit),
+        value(DigitOrNewline::Newline, line_ending),
+    ));
+
+    map(
+        fold_many0(digit_or_newline, Vec2dBuilder::new, |mut b, d| {
+            match d {
+                DigitOrNewline::Digit(x) => b.push(x),
+                DigitOrNewline::Newline => b.finish_row(),
+            }
+            b
+        }),
+        Vec2dBuilder::build,
+    )(input)
+}
+
 macro_rules! sep_arrays {
     ($( $name:ident, $num:literal ),*) => {
         $(
@@ -63,11 +97,10 @@ macro_rules! sep_arrays {
 
 sep_arrays! {
     sep_array_4, 4,
-    sep_array_5, 5,
     sep_array_10, 10
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Vec2d<T> {
     data: Vec<T>,
     width: usize,
@@ -84,8 +117,13 @@ impl<T> Vec2d<T> {
         }
     }
 
+    /// The value at `coord`, or `None` if it's outside the grid.
     pub fn get(&self, coord: (usize, usize)) -> Option<&T> {
-        self.data.get(coord_to_idx(coord, self.width))
+        if coord.0 < self.width {
+            self.data.get(coord_to_idx(coord, self.width))
+        } else {
+            None
+        }
     }
 
     pub fn map<F, U>(self, f: F) -> Vec2d<U>
@@ -107,8 +145,21 @@ impl<T> Vec2d<T> {
         self.width
     }
 
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub fn height(&self) -> usize {
-        self.data.len() / self.width
+        // A grid with no columns has no cells, however many rows it was made with.
+        if self.is_empty() {
+            0
+        } else {
+            self.data.len() / self.width
+        }
     }
 
     pub fn left(&self, (col, row): (usize, usize)) -> Option<(usize, usize)> {
@@ -226,3 +277,814 @@ impl<T> Default for Vec2dBuilder<T> {
         }
     }
 }
+
+/// A point or vector in `N`-dimensional space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point<T, const N: usize>(pub [T; N]);
+
+impl<T, const N: usize> Point<T, N> {
+    pub fn map<U, F>(self, f: F) -> Point<U, N>
+    where
+        F: FnMut(T) -> U,
+    {
+        Point(self.0.map(f))
+    }
+
+    fn zip_with<U, V, F>(self, rhs: Point<U, N>, mut f: F) -> Point<V, N>
+    where
+        F: FnMut(T, U) -> V,
+    {
+        let mut rhs = rhs.0.into_iter();
+        self.map(|a| f(a, rhs.next().unwrap()))
+    }
+}
+
+impl<T: Copy> Point<T, 2> {
+    pub fn new(x: T, y: T) -> Self {
+        Self([x, y])
+    }
+
+    pub fn x(self) -> T {
+        self.0[0]
+    }
+
+    pub fn y(self) -> T {
+        self.0[1]
+    }
+}
+
+impl<T: Copy> Point<T, 3> {
+    pub fn new(x: T, y: T, z: T) -> Self {
+        Self([x, y, z])
+    }
+
+    pub fn x(self) -> T {
+        self.0[0]
+    }
+
+    pub fn y(self) -> T {
+        self.0[1]
+    }
+
+    pub fn z(self) -> T {
+        self.0[2]
+    }
+}
+
+impl<T, const N: usize> Point<T, N>
+where
+    T: Copy + PartialOrd + num::Zero + ops::Sub<Output = T>,
+{
+    fn abs_diffs(self, rhs: Self) -> impl Iterator<Item = T> {
+        self.zip_with(rhs, |a, b| if a > b { a - b } else { b - a })
+            .0
+            .into_iter()
+    }
+
+    pub fn manhattan_distance(self, rhs: Self) -> T {
+        self.abs_diffs(rhs).fold(T::zero(), |acc, d| acc + d)
+    }
+
+    pub fn chebyshev_distance(self, rhs: Self) -> T {
+        self.abs_diffs(rhs)
+            .fold(T::zero(), |acc, d| if d > acc { d } else { acc })
+    }
+}
+
+impl<T, const N: usize> Point<T, N>
+where
+    T: Copy + num::One + num::CheckedAdd + num::CheckedSub,
+{
+    /// Moves one unit along an axis, returning `None` if that would leave the range of `T`.
+    fn step(mut self, axis: usize, forward: bool) -> Option<Self> {
+        let val = &mut self.0[axis];
+        *val = if forward {
+            val.checked_add(&T::one())?
+        } else {
+            val.checked_sub(&T::one())?
+        };
+        Some(self)
+    }
+
+    /// The points one unit away along a single axis, in order of axis and then direction.
+    pub fn neighbors(self) -> impl Iterator<Item = Self> {
+        (0..N)
+            .flat_map(move |axis| [self.step(axis, false), self.step(axis, true)])
+            .flatten()
+    }
+
+    /// This point and every point touching it, including diagonally.
+    ///
+    /// Points are ordered with the first axis varying fastest, so in two dimensions this is
+    /// reading order.
+    pub fn neighborhood(self) -> impl Iterator<Item = Self> {
+        (0..3usize.pow(N as u32)).filter_map(move |mut idx| {
+            let mut point = self;
+            for axis in 0..N {
+                point = match idx % 3 {
+                    0 => point.step(axis, false)?,
+                    2 => point.step(axis, true)?,
+                    _ => point,
+                };
+                idx /= 3;
+            }
+            Some(point)
+        })
+    }
+
+    /// Every point touching this one, including diagonally.
+    pub fn all_neighbors(self) -> impl Iterator<Item = Self>
+    where
+        T: PartialEq,
+    {
+        self.neighborhood().filter(move |p| *p != self)
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Point<T, N> {
+    fn from(coords: [T; N]) -> Self {
+        Self(coords)
+    }
+}
+
+impl<T> From<(T, T)> for Point<T, 2> {
+    fn from((x, y): (T, T)) -> Self {
+        Self([x, y])
+    }
+}
+
+impl<T> From<(T, T, T)> for Point<T, 3> {
+    fn from((x, y, z): (T, T, T)) -> Self {
+        Self([x, y, z])
+    }
+}
+
+impl<T, const N: usize> ops::Index<usize> for Point<T, N> {
+    type Output = T;
+
+    fn index(&self, axis: usize) -> &Self::Output {
+        &self.0[axis]
+    }
+}
+
+impl<T, const N: usize> ops::IndexMut<usize> for Point<T, N> {
+    fn index_mut(&mut self, axis: usize) -> &mut Self::Output {
+        &mut self.0[axis]
+    }
+}
+
+impl<T: ops::Add<Output = T>, const N: usize> ops::Add for Point<T, N> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.zip_with(rhs, |a, b| a + b)
+    }
+}
+
+impl<T: ops::Sub<Output = T>, const N: usize> ops::Sub for Point<T, N> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.zip_with(rhs, |a, b| a - b)
+    }
+}
+
+impl<T: ops::Neg<Output = T>, const N: usize> ops::Neg for Point<T, N> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.map(|a| -a)
+    }
+}
+
+impl<T: Copy + ops::Mul<Output = T>, const N: usize> ops::Mul<T> for Point<T, N> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        self.map(|a| a * rhs)
+    }
+}
+
+impl<T: Copy + ops::Add<Output = T>, const N: usize> ops::AddAssign for Point<T, N> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: Copy + ops::Sub<Output = T>, const N: usize> ops::SubAssign for Point<T, N> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+/// An axis-aligned box, including both of its corners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoundingBox<T, const N: usize> {
+    pub min: Point<T, N>,
+    pub max: Point<T, N>,
+}
+
+impl<T: Copy + Ord, const N: usize> BoundingBox<T, N> {
+    /// Creates the box with the given opposite corners.
+    pub fn new(a: Point<T, N>, b: Point<T, N>) -> Self {
+        Self {
+            min: a.zip_with(b, T::min),
+            max: a.zip_with(b, T::max),
+        }
+    }
+
+    /// The smallest box containing every point, or `None` if there are no points.
+    pub fn around<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Point<T, N>>,
+    {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), Self::including))
+    }
+
+    /// The smallest box containing both this box and the point.
+    pub fn including(self, point: Point<T, N>) -> Self {
+        Self {
+            min: self.min.zip_with(point, T::min),
+            max: self.max.zip_with(point, T::max),
+        }
+    }
+
+    pub fn contains(&self, point: Point<T, N>) -> bool {
+        (0..N).all(|axis| self.min[axis] <= point[axis] && point[axis] <= self.max[axis])
+    }
+
+    /// Grows the box by `amount` in every direction.
+    pub fn expanded(self, amount: T) -> Self
+    where
+        T: ops::Add<Output = T> + ops::Sub<Output = T>,
+    {
+        Self {
+            min: self.min.map(|a| a - amount),
+            max: self.max.map(|a| a + amount),
+        }
+    }
+
+    /// Every point in the box, with the first axis varying fastest.
+    pub fn points(self) -> impl Iterator<Item = Point<T, N>>
+    where
+        T: num::One + ops::Add<Output = T>,
+    {
+        let mut next = Some(self.min);
+        std::iter::from_fn(move || {
+            let current = next?;
+            next = self.after(current);
+            Some(current)
+        })
+    }
+
+    fn after(&self, mut point: Point<T, N>) -> Option<Point<T, N>>
+    where
+        T: num::One + ops::Add<Output = T>,
+    {
+        for axis in 0..N {
+            if point[axis] < self.max[axis] {
+                point[axis] = point[axis] + T::one();
+                return Some(point);
+            }
+            point[axis] = self.min[axis];
+        }
+        None
+    }
+}
+
+/// A set of integers stored as sorted, disjoint, inclusive intervals.
+///
+/// Intervals that overlap or touch are always merged, so two sets are equal exactly when they
+/// contain the same integers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IntervalSet {
+    intervals: Vec<(i64, i64)>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every integer in `range` to the set.
+    pub fn insert(&mut self, range: RangeInclusive<i64>) {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return;
+        }
+
+        // Everything from `lo` on ends at or after `start - 1`, and everything before `hi` starts
+        // at or before `end + 1`, so the intervals in between merge with the new one.
+        let lo = self
+            .intervals
+            .partition_point(|&(_, e)| e.saturating_add(1) < start);
+        let hi = self
+            .intervals
+            .partition_point(|&(s, _)| s.saturating_sub(1) <= end);
+
+        let (start, end) = self.intervals[lo..hi]
+            .iter()
+            .fold((start, end), |(s, e), &(s2, e2)| (s.min(s2), e.max(e2)));
+        self.intervals.splice(lo..hi, [(start, end)]);
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        let idx = self.intervals.partition_point(|&(_, e)| e < value);
+        matches!(self.intervals.get(idx), Some(&(s, _)) if s <= value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// The number of integers in the set. This is a `u128` since the whole range of `i64` holds
+    /// one more integer than fits in a `u64`.
+    pub fn len(&self) -> u128 {
+        self.intervals
+            .iter()
+            .map(|&(s, e)| u128::from(e.abs_diff(s)) + 1)
+            .sum()
+    }
+
+    /// The merged intervals, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = RangeInclusive<i64>> + '_ {
+        self.intervals.iter().map(|&(s, e)| s..=e)
+    }
+
+    pub fn union(&self, rhs: &Self) -> Self {
+        let mut union = self.clone();
+        for range in rhs.iter() {
+            union.insert(range);
+        }
+        union
+    }
+
+    pub fn intersection(&self, rhs: &Self) -> Self {
+        let mut intervals = Vec::new();
+        let (mut a, mut b) = (
+            self.intervals.iter().peekable(),
+            rhs.intervals.iter().peekable(),
+        );
+        while let (Some(&&(s1, e1)), Some(&&(s2, e2))) = (a.peek(), b.peek()) {
+            let (start, end) = (s1.max(s2), e1.min(e2));
+            if start <= end {
+                intervals.push((start, end));
+            }
+            if e1 < e2 {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        Self { intervals }
+    }
+
+    pub fn difference(&self, rhs: &Self) -> Self {
+        match (self.intervals.first(), self.intervals.last()) {
+            (Some(&(start, _)), Some(&(_, end))) => self.intersection(&rhs.complement(start..=end)),
+            _ => Self::new(),
+        }
+    }
+
+    /// Every integer in `bounds` that isn't in the set.
+    pub fn complement(&self, bounds: RangeInclusive<i64>) -> Self {
+        let (start, end) = bounds.into_inner();
+        let mut intervals = Vec::new();
+        // The smallest integer not yet accounted for, or `None` once that would be past `i64::MAX`.
+        let mut next = Some(start);
+        for &(s, e) in &self.intervals {
+            let from = match next {
+                Some(from) if from <= end => from,
+                _ => break,
+            };
+            if s > from {
+                intervals.push((from, (s - 1).min(end)));
+            }
+            if e >= from {
+                next = e.checked_add(1);
+            }
+        }
+        if let Some(from) = next.filter(|&from| from <= end) {
+            intervals.push((from, end));
+        }
+        Self { intervals }
+    }
+}
+
+impl From<RangeInclusive<i64>> for IntervalSet {
+    fn from(range: RangeInclusive<i64>) -> Self {
+        let mut set = Self::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl FromIterator<RangeInclusive<i64>> for IntervalSet {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = RangeInclusive<i64>>,
+    {
+        let mut set = Self::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+impl IntoIterator for IntervalSet {
+    type Item = RangeInclusive<i64>;
+    type IntoIter = std::iter::Map<std::vec::IntoIter<(i64, i64)>, fn((i64, i64)) -> Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.intervals.into_iter().map(|(s, e)| s..=e)
+    }
+}
+
+/// A union-find structure over the elements `0..len`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisjointSet {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl DisjointSet {
+    /// Creates `len` singleton sets.
+    pub fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+            sizes: vec![1; len],
+        }
+    }
+
+    /// Returns the representative of the set containing `elem`.
+    pub fn find(&mut self, elem: usize) -> usize {
+        let mut root = elem;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Point everything on the way straight at the root.
+        let mut cur = elem;
+        while cur != root {
+            cur = std::mem::replace(&mut self.parents[cur], root);
+        }
+
+        root
+    }
+
+    /// Merges the sets containing `a` and `b`, returning `false` if they were already the same.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        let (big, small) = if self.sizes[a] >= self.sizes[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[small] = big;
+        self.sizes[big] += self.sizes[small];
+        true
+    }
+
+    /// The number of elements in the set containing `elem`.
+    pub fn size(&mut self, elem: usize) -> usize {
+        let root = self.find(elem);
+        self.sizes[root]
+    }
+}
+
+/// Something that evolves one step at a time.
+pub trait Simulation {
+    /// Advances the simulation by one step.
+    fn step(&mut self);
+
+    fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Steps until `done` holds, returning the number of steps taken.
+    fn run_until<F>(&mut self, mut done: F) -> usize
+    where
+        Self: Sized,
+        F: FnMut(&Self) -> bool,
+    {
+        let mut steps = 0;
+        while !done(self) {
+            self.step();
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Runs `steps` steps, skipping ahead by whole periods once the state starts repeating.
+    fn run_with_cycles(&mut self, steps: usize)
+    where
+        Self: Sized + Clone + Eq + Hash,
+    {
+        let mut seen = HashMap::new();
+        for i in 0..steps {
+            if let Some(start) = seen.insert(self.clone(), i) {
+                self.run((steps - i) % (i - start));
+                return;
+            }
+            self.step();
+        }
+    }
+
+    /// Finds where the states starting from this one begin to repeat, using Brent's algorithm so
+    /// only a couple of states are kept at once.
+    ///
+    /// Returns `None` if no state repeats within `limit` steps.
+    fn find_cycle(&self, limit: usize) -> Option<Cycle>
+    where
+        Self: Sized + Clone + Eq,
+    {
+        // Find the period by moving the tortoise up to the hare at each power of two.
+        let mut power = 1;
+        let mut period = 1;
+        let mut tortoise = self.clone();
+        let mut hare = self.clone();
+        hare.step();
+        let mut steps = 1;
+        while tortoise != hare {
+            if steps > limit {
+                return None;
+            }
+            if power == period {
+                tortoise = hare.clone();
+                power *= 2;
+                period = 0;
+            }
+            hare.step();
+            period += 1;
+            steps += 1;
+        }
+
+        // With the hare a period ahead, they meet at the start of the cycle.
+        let mut tortoise = self.clone();
+        let mut hare = self.clone();
+        hare.run(period);
+        let mut start = 0;
+        while tortoise != hare {
+            tortoise.step();
+            hare.step();
+            start += 1;
+        }
+
+        Some(Cycle { start, period })
+    }
+}
+
+/// Where a simulation starts repeating: the state after `start + period` steps is the same as the
+/// state after `start` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+/// A small deterministic random number generator, for tests that need lots of varied input.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+#[cfg(test)]
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// A number in `0..bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) % bound
+    }
+
+    /// Puts `items` in a random order, with a Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_grids_have_no_rows() {
+        let grid = Vec2d::repeat(0u8, 0, 3);
+        assert!(grid.is_empty());
+        assert_eq!(0, grid.height());
+        assert_eq!(0, grid.all_coords().count());
+    }
+
+    #[test]
+    fn get_stays_in_the_grid() {
+        let grid = Vec2d::repeat(1u8, 3, 2).map(u32::from);
+        assert_eq!(Some(&1), grid.get((2, 1)));
+        assert_eq!(None, grid.get((3, 0)));
+        assert_eq!(None, grid.get((0, 2)));
+    }
+
+    #[test]
+    fn point_arithmetic_works() {
+        let a = Point([1i64, -2, 3]);
+        let b = Point([4, 5, -6]);
+        assert_eq!(Point([5, 3, -3]), a + b);
+        assert_eq!(Point([-3, -7, 9]), a - b);
+        assert_eq!(Point([-1, 2, -3]), -a);
+        assert_eq!(Point([2, -4, 6]), a * 2);
+    }
+
+    #[test]
+    fn distances_are_correct() {
+        let a = Point([1105i64, -1205, 1229]);
+        let b = Point([-92, -2380, -20]);
+        assert_eq!(3621, a.manhattan_distance(b));
+        assert_eq!(1249, a.chebyshev_distance(b));
+        assert_eq!(7, Point([3u64, 4]).manhattan_distance(Point([0, 0])));
+    }
+
+    #[test]
+    fn neighbors_stay_in_range() {
+        let neighbors = Point([0u64, 5]).neighbors().collect::<Vec<_>>();
+        assert_eq!(vec![Point([1, 5]), Point([0, 4]), Point([0, 6])], neighbors);
+    }
+
+    #[test]
+    fn neighborhood_is_in_reading_order() {
+        let neighborhood = Point([0i64, 0]).neighborhood().collect::<Vec<_>>();
+        let expected = [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (0, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ]
+        .map(Point::from);
+        assert_eq!(&expected[..], &neighborhood[..]);
+        assert_eq!(26, Point([0i64, 0, 0]).all_neighbors().count());
+    }
+
+    #[test]
+    fn bounding_box_covers_points() {
+        let points = [Point([3i64, -1]), Point([-2, 4]), Point([0, 0])];
+        let bounds = BoundingBox::around(points).unwrap();
+        assert_eq!(Point([-2, -1]), bounds.min);
+        assert_eq!(Point([3, 4]), bounds.max);
+        assert!(points.iter().all(|p| bounds.contains(*p)));
+        assert!(!bounds.contains(Point([4, 0])));
+        assert_eq!(36, bounds.points().count());
+        assert_eq!(
+            BoundingBox::new(Point([-3, -2]), Point([4, 5])),
+            bounds.expanded(1)
+        );
+    }
+
+    #[test]
+    fn interval_set_merges_touching_intervals() {
+        let set = [5..=7, 1..=2, 3..=3, 10..=12, 11..=15]
+            .into_iter()
+            .collect::<IntervalSet>();
+        assert_eq!(vec![1..=3, 5..=7, 10..=15], set.iter().collect::<Vec<_>>());
+        assert_eq!(12, set.len());
+        assert!(set.contains(6));
+        assert!(!set.contains(4));
+        assert!(!set.contains(16));
+    }
+
+    #[test]
+    fn rng_is_deterministic() {
+        let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+        let values = (0..100).map(|_| a.below(10)).collect::<Vec<_>>();
+        assert_eq!(values, (0..100).map(|_| b.below(10)).collect::<Vec<_>>());
+        assert!(values.iter().all(|&n| n < 10));
+
+        let mut items = (0..20).collect::<Vec<_>>();
+        a.shuffle(&mut items);
+        assert_ne!((0..20).collect::<Vec<_>>(), items);
+        items.sort_unstable();
+        assert_eq!((0..20).collect::<Vec<_>>(), items);
+    }
+
+    #[test]
+    fn interval_set_len_covers_all_of_i64() {
+        let set = IntervalSet::from(i64::MIN..=i64::MAX);
+        assert_eq!(1 << 64, set.len());
+        assert_eq!(2, IntervalSet::from(i64::MAX - 1..=i64::MAX).len());
+    }
+
+    #[test]
+    fn interval_set_operations_are_correct() {
+        let a = [0..=10, 20..=30].into_iter().collect::<IntervalSet>();
+        let b = [5..=25].into_iter().collect::<IntervalSet>();
+        assert_eq!(IntervalSet::from(0..=30), a.union(&b));
+        assert_eq!(
+            [5..=10, 20..=25].into_iter().collect::<IntervalSet>(),
+            a.intersection(&b)
+        );
+        assert_eq!(
+            [0..=4, 26..=30].into_iter().collect::<IntervalSet>(),
+            a.difference(&b)
+        );
+        assert_eq!(IntervalSet::from(11..=19), b.difference(&a));
+    }
+
+    #[test]
+    fn interval_set_complement_stays_in_bounds() {
+        let set = [0..=10, 20..=30].into_iter().collect::<IntervalSet>();
+        assert_eq!(
+            [-5..=-1, 11..=19].into_iter().collect::<IntervalSet>(),
+            set.complement(-5..=25)
+        );
+        assert_eq!(IntervalSet::new(), set.complement(2..=8));
+        let full = IntervalSet::from(i64::MIN..=i64::MAX);
+        assert!(full.complement(i64::MIN..=i64::MAX).is_empty());
+        assert_eq!(
+            IntervalSet::from(i64::MIN..=-1),
+            IntervalSet::from(0..=i64::MAX).complement(i64::MIN..=i64::MAX)
+        );
+    }
+
+    #[test]
+    fn disjoint_set_merges_sets() {
+        let mut set = DisjointSet::new(6);
+        assert!(set.union(0, 1));
+        assert!(set.union(2, 3));
+        assert!(set.union(1, 3));
+        assert!(!set.union(0, 2));
+        assert_eq!(set.find(0), set.find(3));
+        assert_ne!(set.find(0), set.find(4));
+        assert_eq!(4, set.size(2));
+        assert_eq!(1, set.size(5));
+    }
+
+    #[test]
+    fn disjoint_set_handles_long_chains() {
+        let len = 1_000_000;
+        let mut set = DisjointSet::new(len);
+        for i in 1..len {
+            set.parents[i] = i - 1;
+        }
+        assert_eq!(0, set.find(len - 1));
+        assert_eq!(0, set.parents[len - 1]);
+    }
+
+    /// Counts up to 4, then loops back around to 2.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    struct Looping(u64);
+
+    impl Simulation for Looping {
+        fn step(&mut self) {
+            self.0 = if self.0 < 4 { self.0 + 1 } else { 2 };
+        }
+    }
+
+    #[test]
+    fn cycles_are_found() {
+        let expected = Cycle {
+            start: 2,
+            period: 3,
+        };
+        assert_eq!(Some(expected), Looping(0).find_cycle(100));
+        assert_eq!(None, Looping(0).find_cycle(3));
+    }
+
+    #[test]
+    fn long_runs_skip_ahead() {
+        for steps in [0, 1, 2, 7, 1_000_000_000] {
+            let mut state = Looping(0);
+            state.run_with_cycles(steps);
+            let expected = if steps < 2 {
+                steps
+            } else {
+                2 + (steps - 2) % 3
+            };
+            assert_eq!(Looping(expected as u64), state);
+        }
+    }
+
+    #[test]
+    fn runs_stop_when_asked() {
+        let mut state = Looping(0);
+        assert_eq!(4, state.run_until(|s| s.0 == 4));
+        assert_eq!(Looping(4), state);
+    }
+}